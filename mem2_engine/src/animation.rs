@@ -0,0 +1,299 @@
+//! `animation.rs` - the timing of the flip, match and mismatch animations of the cards.
+//!
+//! The game logic changes the card at once. The animation only shows it and never changes the game.
+//! Every card has a short timeline: the next animation starts after the previous one ends,
+//! so the mismatch shake waits for the flip and the flip down waits for the shake.
+//! The client renders the timeline as CSS animations with delays, so no render loop is needed.
+//! Until its flip down starts, a card face down still shows the face it had.
+//! A card in the middle of its animation does not accept clicks.
+
+///the animations of one card
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CardAnimation {
+    ///the face turns up
+    FlipUp,
+    ///the face turns down
+    FlipDown,
+    ///the pair is found: the card jumps and glows
+    Match,
+    ///the pair is wrong: the card shakes
+    Mismatch,
+}
+
+impl CardAnimation {
+    ///the CSS keyframes name in mem2.css
+    #[must_use]
+    pub const fn css_name(self) -> &'static str {
+        match self {
+            Self::FlipUp => "card_flip_up",
+            Self::FlipDown => "card_flip_down",
+            Self::Match => "card_match",
+            Self::Mismatch => "card_mismatch",
+        }
+    }
+    ///how long the animation lasts
+    #[must_use]
+    pub const fn duration_ms(self) -> f64 {
+        match self {
+            Self::FlipUp | Self::FlipDown => 400.0,
+            Self::Match => 800.0,
+            Self::Mismatch => 500.0,
+        }
+    }
+}
+
+///one animation at its time
+#[derive(Clone, Copy, Debug)]
+struct Scheduled {
+    ///what
+    animation: CardAnimation,
+    ///the start time from js `Date.now()`
+    start: f64,
+}
+
+impl Scheduled {
+    ///the end time
+    fn end(self) -> f64 {
+        self.start + self.animation.duration_ms()
+    }
+}
+
+///the timeline of one card
+#[derive(Clone, Default, Debug)]
+struct Timeline {
+    ///the time when the timeline was rendered the first time. The CSS delays are relative to it.
+    base: f64,
+    ///the animations in order
+    vec_scheduled: Vec<Scheduled>,
+    ///the card number shown until the flip down starts
+    face_before_flip_down: usize,
+}
+
+///the timelines of all the cards. Index is the card index.
+#[derive(Default, Debug)]
+pub struct CardAnimations {
+    ///index 0 is not a card and stays empty
+    vec_timelines: Vec<Timeline>,
+}
+
+impl CardAnimations {
+    ///a new game forgets all the animations
+    pub fn clear(&mut self) {
+        self.vec_timelines.clear();
+    }
+
+    ///the end of the last animation of the card. Now if it has none.
+    fn last_end(&self, card_index: usize, now: f64) -> f64 {
+        self.vec_timelines
+            .get(card_index)
+            .and_then(|timeline| timeline.vec_scheduled.last())
+            .map_or(now, |scheduled| scheduled.end().max(now))
+    }
+
+    ///the animation starts after the last animation of the card
+    pub fn schedule(&mut self, card_index: usize, animation: CardAnimation, now: f64) {
+        self.schedule_together(&[card_index], animation, now);
+    }
+
+    ///the cards start the animation together after the last animation of every card.
+    ///
+    ///The two cards of a pair shake or celebrate at the same time.
+    pub fn schedule_together(
+        &mut self,
+        card_indexes: &[usize],
+        animation: CardAnimation,
+        now: f64,
+    ) {
+        let start = card_indexes
+            .iter()
+            .map(|card_index| self.last_end(*card_index, now))
+            .fold(now, f64::max);
+        for card_index in card_indexes.iter().copied() {
+            if self.vec_timelines.len() <= card_index {
+                self.vec_timelines
+                    .resize(card_index + 1, Timeline::default());
+            }
+            let is_running = self.last_end(card_index, now) > now;
+            if let Some(timeline) = self.vec_timelines.get_mut(card_index) {
+                //a finished timeline starts again. A running one is extended and its rendered delays stay the same.
+                if !is_running {
+                    timeline.base = now;
+                    timeline.vec_scheduled.clear();
+                }
+                timeline.vec_scheduled.push(Scheduled { animation, start });
+            }
+        }
+    }
+
+    ///the cards flip down together after their last animations.
+    ///
+    ///The card index and the card number: until the flip starts the card still shows its face.
+    pub fn schedule_flip_down(&mut self, vec_faces: &[(usize, usize)], now: f64) {
+        let card_indexes: Vec<usize> = vec_faces
+            .iter()
+            .map(|(card_index, _)| *card_index)
+            .collect();
+        self.schedule_together(&card_indexes, CardAnimation::FlipDown, now);
+        for (card_index, card_number) in vec_faces.iter().copied() {
+            if let Some(timeline) = self.vec_timelines.get_mut(card_index) {
+                timeline.face_before_flip_down = card_number;
+            }
+        }
+    }
+
+    ///the flip down of the card that has not started yet
+    fn pending_flip_down(&self, card_index: usize, now: f64) -> Option<&Scheduled> {
+        self.vec_timelines.get(card_index).and_then(|timeline| {
+            timeline.vec_scheduled.iter().find(|scheduled| {
+                scheduled.animation == CardAnimation::FlipDown && scheduled.start > now
+            })
+        })
+    }
+
+    ///the card number the card face down still shows, because its flip down has not started yet
+    #[must_use]
+    pub fn face_before_flip_down(&self, card_index: usize, now: f64) -> Option<usize> {
+        self.pending_flip_down(card_index, now)?;
+        self.vec_timelines
+            .get(card_index)
+            .map(|timeline| timeline.face_before_flip_down)
+            .filter(|card_number| *card_number != 0)
+    }
+
+    ///how long until the next card changes its face. The client renders again then.
+    #[must_use]
+    pub fn ms_until_face_change(&self, now: f64) -> Option<f64> {
+        (0..self.vec_timelines.len())
+            .filter_map(|card_index| self.pending_flip_down(card_index, now))
+            .map(|scheduled| scheduled.start - now)
+            .fold(None, |min, delay| {
+                Some(min.map_or(delay, |min: f64| min.min(delay)))
+            })
+    }
+
+    ///the card is animated now or will be. A click on it waits.
+    #[must_use]
+    pub fn is_busy(&self, card_index: usize, now: f64) -> bool {
+        self.vec_timelines
+            .get(card_index)
+            .and_then(|timeline| timeline.vec_scheduled.last())
+            .is_some_and(|scheduled| scheduled.end() > now)
+    }
+
+    ///the CSS animation properties of the card. None if its animations are finished.
+    #[must_use]
+    pub fn css_style(&self, card_index: usize, now: f64) -> Option<String> {
+        let timeline = self.vec_timelines.get(card_index)?;
+        if !self.is_busy(card_index, now) {
+            return None;
+        }
+        let join = |property: &dyn Fn(&Scheduled) -> String| {
+            timeline
+                .vec_scheduled
+                .iter()
+                .map(property)
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        Some(format!(
+            "animation-name: {}; animation-duration: {}; animation-delay: {};",
+            join(&|scheduled| scheduled.animation.css_name().to_string()),
+            join(&|scheduled| format!("{:.0}ms", scheduled.animation.duration_ms())),
+            join(&|scheduled| format!("{:.0}ms", scheduled.start - timeline.base)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animations_of_a_card_are_chained() {
+        let mut card_animations = CardAnimations::default();
+        card_animations.schedule(1, CardAnimation::FlipUp, 1000.0);
+        card_animations.schedule(1, CardAnimation::Mismatch, 1000.0);
+        card_animations.schedule(1, CardAnimation::FlipDown, 1000.0);
+        assert_eq!(
+            card_animations.css_style(1, 1000.0).unwrap(),
+            "animation-name: card_flip_up, card_mismatch, card_flip_down; \
+             animation-duration: 400ms, 500ms, 400ms; animation-delay: 0ms, 400ms, 900ms;"
+        );
+    }
+
+    #[test]
+    fn pair_starts_together_after_the_later_card() {
+        let mut card_animations = CardAnimations::default();
+        card_animations.schedule(1, CardAnimation::FlipUp, 1000.0);
+        card_animations.schedule(2, CardAnimation::FlipUp, 1200.0);
+        card_animations.schedule_together(&[1, 2], CardAnimation::Match, 1200.0);
+        //the first card waits for the flip of the second card
+        assert!(card_animations
+            .css_style(1, 1200.0)
+            .unwrap()
+            .ends_with("animation-delay: 0ms, 600ms;"));
+        assert!(card_animations
+            .css_style(2, 1200.0)
+            .unwrap()
+            .ends_with("animation-delay: 0ms, 400ms;"));
+    }
+
+    #[test]
+    fn running_timeline_keeps_its_base() {
+        let mut card_animations = CardAnimations::default();
+        card_animations.schedule(1, CardAnimation::FlipUp, 1000.0);
+        //in the middle of the flip the delays are still relative to its start
+        card_animations.schedule(1, CardAnimation::Mismatch, 1200.0);
+        assert!(card_animations
+            .css_style(1, 1200.0)
+            .unwrap()
+            .ends_with("animation-delay: 0ms, 400ms;"));
+    }
+
+    #[test]
+    fn finished_timeline_starts_again() {
+        let mut card_animations = CardAnimations::default();
+        card_animations.schedule(1, CardAnimation::FlipUp, 1000.0);
+        card_animations.schedule(1, CardAnimation::FlipDown, 5000.0);
+        assert_eq!(
+            card_animations.css_style(1, 5000.0).unwrap(),
+            "animation-name: card_flip_down; animation-duration: 400ms; animation-delay: 0ms;"
+        );
+    }
+
+    #[test]
+    fn card_is_busy_until_the_last_animation_ends() {
+        let mut card_animations = CardAnimations::default();
+        assert!(!card_animations.is_busy(1, 1000.0));
+        card_animations.schedule(1, CardAnimation::FlipUp, 1000.0);
+        card_animations.schedule(1, CardAnimation::Match, 1000.0);
+        assert!(card_animations.is_busy(1, 2100.0));
+        assert!(!card_animations.is_busy(1, 2200.0));
+        assert_eq!(card_animations.css_style(1, 2200.0), None);
+        //the other cards are not busy
+        assert!(!card_animations.is_busy(2, 1000.0));
+    }
+
+    #[test]
+    fn face_stays_up_during_the_shake() {
+        let mut card_animations = CardAnimations::default();
+        card_animations.schedule_together(&[1, 2], CardAnimation::Mismatch, 1000.0);
+        card_animations.schedule_flip_down(&[(1, 7), (2, 9)], 1100.0);
+        assert_eq!(card_animations.face_before_flip_down(1, 1100.0), Some(7));
+        assert_eq!(card_animations.face_before_flip_down(2, 1499.0), Some(9));
+        //the back shows from the start of the flip down
+        assert_eq!(card_animations.face_before_flip_down(1, 1500.0), None);
+        let delay = card_animations.ms_until_face_change(1100.0).unwrap();
+        assert!((delay - 400.0).abs() < f64::EPSILON);
+        assert_eq!(card_animations.ms_until_face_change(1500.0), None);
+    }
+
+    #[test]
+    fn flip_down_after_the_shake_shows_the_back_at_once() {
+        let mut card_animations = CardAnimations::default();
+        card_animations.schedule_together(&[1, 2], CardAnimation::Mismatch, 1000.0);
+        card_animations.schedule_flip_down(&[(1, 7), (2, 9)], 3000.0);
+        assert_eq!(card_animations.face_before_flip_down(1, 3000.0), None);
+        assert_eq!(card_animations.ms_until_face_change(3000.0), None);
+    }
+}
@@ -0,0 +1,799 @@
+//! `auth.rs` - the messages in a game are authenticated, so nobody else on the relay can forge or replay them.
+//! The two players know the same room secret from the invite link. It never travels over the wire.
+//! Each player sends a random nonce in the invitation handshake.
+//! The session key is derived from the room secret, both players and both nonces, so every game has its own key.
+//! Every game message is sealed with a sequence number and an HMAC-SHA256 of the sender, receiver, sequence and message.
+//! The receiver drops the messages with a wrong MAC and the messages with an old sequence number.
+//! The sealed messages are also reliable. The receiver applies them in the order of the sequence numbers
+//! and holds back the messages that arrive too early. It acknowledges every received message with an `Ack`.
+//! The sender keeps the messages until they are acknowledged and sends them again if the `Ack` does not come.
+//! The sender keeps at most `MAX_UNACKED` messages. Without an `Ack` for so long the other player is gone.
+//! The heartbeat `Ping` and `Pong` are sealed with their own sequence numbers and are not reliable.
+//! A lost heartbeat is not sent again, the next one comes soon. An old heartbeat is dropped.
+//! Before the game, the lobby and the invitation messages are sealed with the room key.
+//! Only the players with the same invite link know it, so nobody else on the relay can forge an invitation.
+
+use crate::{EngineError, WsMessage};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::Serialize;
+use sha2::Sha256;
+
+///HMAC with SHA-256
+type HmacSha256 = Hmac<Sha256>;
+
+///the label of the key derivation, so the key cannot be mistaken for another use of the room secret
+const KEY_LABEL: &[u8] = b"mem2 session key";
+///the label of the room key derivation
+const ROOM_KEY_LABEL: &[u8] = b"mem2 room key";
+///the random bytes of a new room secret. The secret is written in hex.
+const ROOM_SECRET_BYTES: usize = 16;
+///the first byte of the MAC content of a sealed message, so it cannot be mistaken for an `Ack`
+const SEALED_TAG: u8 = 0;
+///the first byte of the MAC content of an `Ack`
+const ACK_TAG: u8 = 1;
+///the first byte of the MAC content of a sealed heartbeat
+const HEARTBEAT_TAG: u8 = 2;
+///the first byte of the MAC content of a message sealed with the room key
+const ROOM_TAG: u8 = 3;
+///the most sealed messages that wait for the `Ack`
+const MAX_UNACKED: usize = 64;
+
+///the secret key of one game between two players
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SessionKey(Vec<u8>);
+
+impl SessionKey {
+    ///derive the key of this game from the room secret.
+    ///
+    ///Player 1 is the player whose proposal was accepted, player 2 accepted it.
+    #[must_use]
+    pub fn derive(room_secret: &str, player1: (usize, u64), player2: (usize, u64)) -> Self {
+        let mut mac = new_mac(room_secret.as_bytes());
+        mac.input(KEY_LABEL);
+        for (ws_client_instance, nonce) in &[player1, player2] {
+            mac.input(&(*ws_client_instance as u64).to_le_bytes());
+            mac.input(&nonce.to_le_bytes());
+        }
+        Self(mac.result().code().to_vec())
+    }
+
+    ///the MAC of the content encoded with bincode
+    fn mac<T: Serialize>(&self, content: &T) -> Result<HmacSha256, EngineError> {
+        content_mac(&self.0, content)
+    }
+}
+
+///a new random room secret for the invite link
+#[must_use]
+pub fn new_room_secret<R: Rng>(rng: &mut R) -> String {
+    const HEX_DIGITS: &[u8] = b"0123456789abcdef";
+    //two hex digits for every byte
+    (0..ROOM_SECRET_BYTES * 2)
+        .map(|_| char::from(HEX_DIGITS[rng.gen_range(0, HEX_DIGITS.len())]))
+        .collect()
+}
+
+///the key of all the players with the same invite link. It authenticates the lobby and the invitation.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RoomKey(Vec<u8>);
+
+impl RoomKey {
+    ///derive the room key from the room secret
+    #[must_use]
+    pub fn derive(room_secret: &str) -> Self {
+        let mut mac = new_mac(room_secret.as_bytes());
+        mac.input(ROOM_KEY_LABEL);
+        Self(mac.result().code().to_vec())
+    }
+
+    ///seal a lobby or invitation message with the MAC
+    ///# Errors
+    ///Returns `InvalidEncoding` if the message cannot be serialized.
+    pub fn seal(&self, msg: WsMessage) -> Result<WsMessage, EngineError> {
+        let mac = content_mac(&self.0, &(ROOM_TAG, &msg))?
+            .result()
+            .code()
+            .to_vec();
+        Ok(WsMessage::RoomSealed {
+            msg: Box::new(msg),
+            mac,
+        })
+    }
+
+    ///check the MAC and return the lobby or invitation message inside
+    ///# Errors
+    ///Returns `ForgedMessage` if the MAC is wrong or the message inside is not a lobby or invitation message.
+    pub fn open(&self, msg: WsMessage, mac: &[u8]) -> Result<WsMessage, EngineError> {
+        if !msg.needs_room_seal() {
+            return Err(EngineError::ForgedMessage);
+        }
+        content_mac(&self.0, &(ROOM_TAG, &msg))?
+            .verify(mac)
+            .map_err(|_| EngineError::ForgedMessage)?;
+        Ok(msg)
+    }
+}
+
+///HMAC accepts keys of any length
+fn new_mac(key: &[u8]) -> HmacSha256 {
+    HmacSha256::new_varkey(key).unwrap_or_else(|_| unreachable!("HMAC accepts keys of any length"))
+}
+
+///the MAC of the content encoded with bincode
+fn content_mac<T: Serialize>(key: &[u8], content: &T) -> Result<HmacSha256, EngineError> {
+    let bytes = bincode::serialize(content).map_err(|_| EngineError::InvalidEncoding)?;
+    let mut mac = new_mac(key);
+    mac.input(&bytes);
+    Ok(mac)
+}
+
+///a sealed message that waits for the `Ack`
+#[derive(Clone, Debug)]
+struct Unacked {
+    ///the sequence number of the sealed message
+    sequence: u64,
+    ///time of the last sending from js `Date.now()`
+    last_sent: f64,
+    ///the sealed message, ready to be sent again
+    sealed: WsMessage,
+}
+
+///the authenticated and reliable connection to the other player in one game
+#[derive(Clone, Debug)]
+pub struct PeerSession {
+    ///the key of this game
+    key: SessionKey,
+    ///my ws client instance
+    my_ws_client_instance: usize,
+    ///the ws client instance of the other player
+    other_ws_client_instance: usize,
+    ///the sequence number of my last sealed message
+    last_sent_sequence: u64,
+    ///the sequence number of the last message from the other player applied in order
+    last_received_sequence: u64,
+    ///my sealed messages not acknowledged yet, in the order of the sequence numbers
+    vec_unacked: Vec<Unacked>,
+    ///the messages from the other player that came before the previous ones
+    vec_early: Vec<(u64, WsMessage)>,
+    ///the sequence number of my last sealed heartbeat
+    last_sent_heartbeat: u64,
+    ///the sequence number of the last heartbeat from the other player
+    last_received_heartbeat: u64,
+}
+
+impl PeerSession {
+    ///the session starts when the invitation is accepted
+    #[must_use]
+    pub const fn new(
+        key: SessionKey,
+        my_ws_client_instance: usize,
+        other_ws_client_instance: usize,
+    ) -> Self {
+        Self {
+            key,
+            my_ws_client_instance,
+            other_ws_client_instance,
+            last_sent_sequence: 0,
+            last_received_sequence: 0,
+            vec_unacked: Vec::new(),
+            vec_early: Vec::new(),
+            last_sent_heartbeat: 0,
+            last_received_heartbeat: 0,
+        }
+    }
+
+    ///seal the message with the next sequence number and the MAC.
+    ///
+    ///The sealed message is kept until the `Ack`.
+    ///# Errors
+    ///Returns `TooManyUnacked` if `MAX_UNACKED` messages wait for the `Ack`.
+    ///Returns `InvalidEncoding` if the message cannot be serialized.
+    pub fn seal(&mut self, msg: WsMessage, now: f64) -> Result<WsMessage, EngineError> {
+        if self.vec_unacked.len() >= MAX_UNACKED {
+            return Err(EngineError::TooManyUnacked);
+        }
+        let sequence = self.last_sent_sequence + 1;
+        let mac = self
+            .key
+            .mac(&(
+                SEALED_TAG,
+                self.my_ws_client_instance,
+                self.other_ws_client_instance,
+                sequence,
+                &msg,
+            ))?
+            .result()
+            .code()
+            .to_vec();
+        self.last_sent_sequence = sequence;
+        let sealed = WsMessage::Sealed {
+            ws_client_instance: self.my_ws_client_instance,
+            other_ws_client_instance: self.other_ws_client_instance,
+            sequence,
+            msg: Box::new(msg),
+            mac,
+        };
+        self.vec_unacked.push(Unacked {
+            sequence,
+            last_sent: now,
+            sealed: sealed.clone(),
+        });
+        Ok(sealed)
+    }
+
+    ///check the MAC and the sequence number and return the messages that can be applied now, in order.
+    ///
+    ///A message that comes too early is held back and the result is empty.
+    ///# Errors
+    ///Returns `ForgedMessage` if the MAC is wrong or the message is not from the other player to me.
+    ///Returns `ReplayedMessage` if the sequence number was already received.
+    pub fn open(
+        &mut self,
+        ws_client_instance: usize,
+        other_ws_client_instance: usize,
+        sequence: u64,
+        msg: WsMessage,
+        mac: &[u8],
+    ) -> Result<Vec<WsMessage>, EngineError> {
+        if ws_client_instance != self.other_ws_client_instance
+            || other_ws_client_instance != self.my_ws_client_instance
+        {
+            return Err(EngineError::ForgedMessage);
+        }
+        self.key
+            .mac(&(
+                SEALED_TAG,
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                &msg,
+            ))?
+            .verify(mac)
+            .map_err(|_| EngineError::ForgedMessage)?;
+        if sequence <= self.last_received_sequence
+            || self.vec_early.iter().any(|(early, _)| *early == sequence)
+        {
+            return Err(EngineError::ReplayedMessage);
+        }
+        self.vec_early.push((sequence, msg));
+        //the next messages in order, as long as there is no gap
+        let mut vec_ready = Vec::new();
+        while let Some(position) = self
+            .vec_early
+            .iter()
+            .position(|(early, _)| *early == self.last_received_sequence + 1)
+        {
+            let (next, msg) = self.vec_early.remove(position);
+            self.last_received_sequence = next;
+            vec_ready.push(msg);
+        }
+        Ok(vec_ready)
+    }
+
+    ///acknowledge all the messages applied in order until now
+    ///# Errors
+    ///Returns `InvalidEncoding` if the `Ack` cannot be serialized.
+    pub fn ack(&self) -> Result<WsMessage, EngineError> {
+        let sequence = self.last_received_sequence;
+        let mac = self
+            .key
+            .mac(&(
+                ACK_TAG,
+                self.my_ws_client_instance,
+                self.other_ws_client_instance,
+                sequence,
+            ))?
+            .result()
+            .code()
+            .to_vec();
+        Ok(WsMessage::Ack {
+            ws_client_instance: self.my_ws_client_instance,
+            other_ws_client_instance: self.other_ws_client_instance,
+            sequence,
+            mac,
+        })
+    }
+
+    ///the other player has all my messages until this sequence number. An old `Ack` changes nothing.
+    ///# Errors
+    ///Returns `ForgedMessage` if the MAC is wrong or the `Ack` is not from the other player to me.
+    pub fn on_ack(
+        &mut self,
+        ws_client_instance: usize,
+        other_ws_client_instance: usize,
+        sequence: u64,
+        mac: &[u8],
+    ) -> Result<(), EngineError> {
+        if ws_client_instance != self.other_ws_client_instance
+            || other_ws_client_instance != self.my_ws_client_instance
+        {
+            return Err(EngineError::ForgedMessage);
+        }
+        self.key
+            .mac(&(
+                ACK_TAG,
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+            ))?
+            .verify(mac)
+            .map_err(|_| EngineError::ForgedMessage)?;
+        self.vec_unacked
+            .retain(|unacked| unacked.sequence > sequence);
+        Ok(())
+    }
+
+    ///the sealed messages without `Ack` for longer than `retransmit_ms`, to be sent again.
+    ///
+    ///After a reconnect `retransmit_ms` is 0 and all of them are sent again.
+    pub fn retransmit(&mut self, now: f64, retransmit_ms: f64) -> Vec<WsMessage> {
+        self.vec_unacked
+            .iter_mut()
+            .filter(|unacked| now - unacked.last_sent >= retransmit_ms)
+            .map(|unacked| {
+                unacked.last_sent = now;
+                unacked.sealed.clone()
+            })
+            .collect()
+    }
+
+    ///seal the `Ping` or `Pong` with the next heartbeat number and the MAC.
+    ///
+    ///It is not kept for the retransmit.
+    ///# Errors
+    ///Returns `InvalidEncoding` if the message cannot be serialized.
+    pub fn seal_heartbeat(&mut self, msg: WsMessage) -> Result<WsMessage, EngineError> {
+        let sequence = self.last_sent_heartbeat + 1;
+        let mac = self
+            .key
+            .mac(&(
+                HEARTBEAT_TAG,
+                self.my_ws_client_instance,
+                self.other_ws_client_instance,
+                sequence,
+                &msg,
+            ))?
+            .result()
+            .code()
+            .to_vec();
+        self.last_sent_heartbeat = sequence;
+        Ok(WsMessage::SealedHeartbeat {
+            ws_client_instance: self.my_ws_client_instance,
+            other_ws_client_instance: self.other_ws_client_instance,
+            sequence,
+            msg: Box::new(msg),
+            mac,
+        })
+    }
+
+    ///check the MAC and the heartbeat number and return the `Ping` or `Pong` inside.
+    ///# Errors
+    ///Returns `ForgedMessage` if the MAC is wrong, the heartbeat is not from the other player to me
+    ///or the message inside is not a `Ping` or `Pong`.
+    ///Returns `ReplayedMessage` if the heartbeat is not newer than the last one.
+    pub fn open_heartbeat(
+        &mut self,
+        ws_client_instance: usize,
+        other_ws_client_instance: usize,
+        sequence: u64,
+        msg: WsMessage,
+        mac: &[u8],
+    ) -> Result<WsMessage, EngineError> {
+        //a game message must not skip the order of the reliable messages
+        let is_heartbeat = matches!(msg, WsMessage::Ping { .. } | WsMessage::Pong { .. });
+        if !is_heartbeat
+            || ws_client_instance != self.other_ws_client_instance
+            || other_ws_client_instance != self.my_ws_client_instance
+        {
+            return Err(EngineError::ForgedMessage);
+        }
+        self.key
+            .mac(&(
+                HEARTBEAT_TAG,
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                &msg,
+            ))?
+            .verify(mac)
+            .map_err(|_| EngineError::ForgedMessage)?;
+        if sequence <= self.last_received_heartbeat {
+            return Err(EngineError::ReplayedMessage);
+        }
+        self.last_received_heartbeat = sequence;
+        Ok(msg)
+    }
+
+    ///how many of my messages wait for the `Ack`
+    #[must_use]
+    pub const fn count_unacked(&self) -> usize {
+        self.vec_unacked.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: usize = 1111;
+    const BOB: usize = 2222;
+
+    ///both ends of one game
+    fn sessions(alice_secret: &str, bob_secret: &str) -> (PeerSession, PeerSession) {
+        let alice_key = SessionKey::derive(alice_secret, (ALICE, 7), (BOB, 9));
+        let bob_key = SessionKey::derive(bob_secret, (ALICE, 7), (BOB, 9));
+        (
+            PeerSession::new(alice_key, ALICE, BOB),
+            PeerSession::new(bob_key, BOB, ALICE),
+        )
+    }
+
+    fn player_change() -> WsMessage {
+        WsMessage::PlayerChange {
+            ws_client_instance: ALICE,
+        }
+    }
+
+    ///the fields of a sealed message
+    fn open(session: &mut PeerSession, sealed: WsMessage) -> Result<Vec<WsMessage>, EngineError> {
+        match sealed {
+            WsMessage::Sealed {
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                msg,
+                mac,
+            } => session.open(
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                *msg,
+                &mac,
+            ),
+            _ => panic!("not sealed"),
+        }
+    }
+
+    #[test]
+    fn sealed_message_is_opened() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let sealed = alice.seal(player_change(), 0.0).unwrap();
+        assert!(matches!(
+            open(&mut bob, sealed).unwrap().as_slice(),
+            [WsMessage::PlayerChange {
+                ws_client_instance: ALICE
+            }]
+        ));
+    }
+
+    #[test]
+    fn wrong_room_secret_is_forged() {
+        let (mut alice, mut bob) = sessions("room", "other room");
+        let sealed = alice.seal(player_change(), 0.0).unwrap();
+        assert_eq!(
+            open(&mut bob, sealed).err(),
+            Some(EngineError::ForgedMessage)
+        );
+    }
+
+    #[test]
+    fn every_game_has_its_own_key() {
+        assert_ne!(
+            SessionKey::derive("room", (ALICE, 7), (BOB, 9)),
+            SessionKey::derive("room", (ALICE, 8), (BOB, 9))
+        );
+    }
+
+    ///the fields of a message sealed with the room key
+    fn open_room(room_key: &RoomKey, sealed: WsMessage) -> Result<WsMessage, EngineError> {
+        match sealed {
+            WsMessage::RoomSealed { msg, mac } => room_key.open(*msg, &mac),
+            _ => panic!("not room sealed"),
+        }
+    }
+
+    fn cancel_invite() -> WsMessage {
+        WsMessage::CancelInvite {
+            ws_client_instance: ALICE,
+        }
+    }
+
+    #[test]
+    fn room_sealed_message_is_opened_only_in_the_same_room() {
+        let sealed = RoomKey::derive("room").seal(cancel_invite()).unwrap();
+        assert!(matches!(
+            open_room(&RoomKey::derive("room"), sealed.clone()),
+            Ok(WsMessage::CancelInvite {
+                ws_client_instance: ALICE
+            })
+        ));
+        assert_eq!(
+            open_room(&RoomKey::derive("other room"), sealed).err(),
+            Some(EngineError::ForgedMessage)
+        );
+    }
+
+    #[test]
+    fn room_key_seals_only_the_lobby_and_the_invitation() {
+        let room_key = RoomKey::derive("room");
+        let sealed = room_key.seal(player_change()).unwrap();
+        //a game message needs the session key
+        assert_eq!(
+            open_room(&room_key, sealed).err(),
+            Some(EngineError::ForgedMessage)
+        );
+    }
+
+    #[test]
+    fn room_key_is_not_a_session_key() {
+        let room_key = RoomKey::derive("room");
+        let session_key = SessionKey::derive("room", (ALICE, 7), (BOB, 9));
+        assert_ne!(room_key.0, session_key.0);
+    }
+
+    #[test]
+    fn new_room_secrets_are_random_hex() {
+        let mut rng = rand::thread_rng();
+        let secret = new_room_secret(&mut rng);
+        assert_eq!(secret.len(), ROOM_SECRET_BYTES * 2);
+        assert!(secret.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(secret, new_room_secret(&mut rng));
+    }
+
+    #[test]
+    fn changed_message_is_forged() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let sealed = match alice.seal(player_change(), 0.0).unwrap() {
+            WsMessage::Sealed {
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                mac,
+                ..
+            } => WsMessage::Sealed {
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                msg: Box::new(WsMessage::PlayerClick {
+                    ws_client_instance: ALICE,
+                    card_index: 1,
+                    count_click_inside_one_turn: 1,
+                }),
+                mac,
+            },
+            _ => panic!("not sealed"),
+        };
+        assert_eq!(
+            open(&mut bob, sealed).err(),
+            Some(EngineError::ForgedMessage)
+        );
+    }
+
+    #[test]
+    fn replayed_message_is_dropped() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let first = alice.seal(player_change(), 0.0).unwrap();
+        let second = alice.seal(player_change(), 0.0).unwrap();
+        assert!(open(&mut bob, first.clone()).is_ok());
+        assert_eq!(
+            open(&mut bob, first).err(),
+            Some(EngineError::ReplayedMessage)
+        );
+        //the second is held back until the first, but it is received only once
+        let (mut alice, mut bob) = sessions("room", "room");
+        let _first = alice.seal(player_change(), 0.0).unwrap();
+        assert!(open(&mut bob, second.clone()).unwrap().is_empty());
+        assert_eq!(
+            open(&mut bob, second).err(),
+            Some(EngineError::ReplayedMessage)
+        );
+    }
+
+    ///the card index of a sealed `PlayerClick`
+    fn card_index(msg: &WsMessage) -> usize {
+        match msg {
+            WsMessage::PlayerClick { card_index, .. } => *card_index,
+            _ => panic!("not a click"),
+        }
+    }
+
+    fn player_click(card_index: usize) -> WsMessage {
+        WsMessage::PlayerClick {
+            ws_client_instance: ALICE,
+            card_index,
+            count_click_inside_one_turn: 1,
+        }
+    }
+
+    #[test]
+    fn early_messages_are_applied_in_order() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let first = alice.seal(player_click(1), 0.0).unwrap();
+        let second = alice.seal(player_click(2), 0.0).unwrap();
+        let third = alice.seal(player_click(3), 0.0).unwrap();
+        assert!(open(&mut bob, third).unwrap().is_empty());
+        assert!(open(&mut bob, second).unwrap().is_empty());
+        let vec_ready = open(&mut bob, first).unwrap();
+        let vec_card_index: Vec<usize> = vec_ready.iter().map(card_index).collect();
+        assert_eq!(vec_card_index, vec![1, 2, 3]);
+    }
+
+    ///the fields of an `Ack`
+    fn on_ack(session: &mut PeerSession, ack: WsMessage) -> Result<(), EngineError> {
+        match ack {
+            WsMessage::Ack {
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                mac,
+            } => session.on_ack(ws_client_instance, other_ws_client_instance, sequence, &mac),
+            _ => panic!("not an ack"),
+        }
+    }
+
+    #[test]
+    fn ack_removes_the_received_messages() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let first = alice.seal(player_click(1), 0.0).unwrap();
+        let _lost = alice.seal(player_click(2), 0.0).unwrap();
+        assert_eq!(alice.count_unacked(), 2);
+        open(&mut bob, first).unwrap();
+        on_ack(&mut alice, bob.ack().unwrap()).unwrap();
+        assert_eq!(alice.count_unacked(), 1);
+    }
+
+    #[test]
+    fn forged_ack_is_rejected() {
+        let (mut alice, _bob) = sessions("room", "room");
+        let (_alice, eve) = sessions("other room", "other room");
+        alice.seal(player_click(1), 0.0).unwrap();
+        let mut forged = eve.ack().unwrap();
+        if let WsMessage::Ack { sequence, .. } = &mut forged {
+            *sequence = 1;
+        }
+        assert_eq!(
+            on_ack(&mut alice, forged).err(),
+            Some(EngineError::ForgedMessage)
+        );
+        assert_eq!(alice.count_unacked(), 1);
+    }
+
+    #[test]
+    fn lost_message_is_sent_again() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let _lost = alice.seal(player_click(1), 1000.0).unwrap();
+        assert!(alice.retransmit(2000.0, 3000.0).is_empty());
+        let vec_again = alice.retransmit(4000.0, 3000.0);
+        assert_eq!(vec_again.len(), 1);
+        //the next retransmit waits again
+        assert!(alice.retransmit(5000.0, 3000.0).is_empty());
+        //after a reconnect everything is sent again
+        assert_eq!(alice.retransmit(5000.0, 0.0).len(), 1);
+        let vec_ready = open(&mut bob, vec_again[0].clone()).unwrap();
+        assert_eq!(card_index(&vec_ready[0]), 1);
+    }
+
+    #[test]
+    fn unacked_messages_are_limited() {
+        let (mut alice, _bob) = sessions("room", "room");
+        for card_index in 0..MAX_UNACKED {
+            alice.seal(player_click(card_index), 0.0).unwrap();
+        }
+        assert_eq!(
+            alice.seal(player_click(1), 0.0).err(),
+            Some(EngineError::TooManyUnacked)
+        );
+        assert_eq!(alice.count_unacked(), MAX_UNACKED);
+    }
+
+    fn ping(sent_at: f64) -> WsMessage {
+        WsMessage::Ping {
+            ws_client_instance: ALICE,
+            other_ws_client_instance: BOB,
+            sent_at,
+        }
+    }
+
+    ///the fields of a sealed heartbeat
+    fn open_heartbeat(
+        session: &mut PeerSession,
+        sealed: WsMessage,
+    ) -> Result<WsMessage, EngineError> {
+        match sealed {
+            WsMessage::SealedHeartbeat {
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                msg,
+                mac,
+            } => session.open_heartbeat(
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                *msg,
+                &mac,
+            ),
+            _ => panic!("not a sealed heartbeat"),
+        }
+    }
+
+    #[test]
+    fn heartbeat_is_not_kept_for_retransmit() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let sealed = alice.seal_heartbeat(ping(1000.0)).unwrap();
+        assert_eq!(alice.count_unacked(), 0);
+        assert!(alice.retransmit(10_000.0, 0.0).is_empty());
+        assert!(matches!(
+            open_heartbeat(&mut bob, sealed),
+            Ok(WsMessage::Ping { sent_at, .. }) if sent_at > 999.0
+        ));
+    }
+
+    #[test]
+    fn lost_heartbeat_does_not_block_the_game() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let _lost = alice.seal_heartbeat(ping(1000.0)).unwrap();
+        let click = alice.seal(player_click(1), 1000.0).unwrap();
+        let next = alice.seal_heartbeat(ping(3000.0)).unwrap();
+        assert_eq!(card_index(&open(&mut bob, click).unwrap()[0]), 1);
+        assert!(open_heartbeat(&mut bob, next).is_ok());
+    }
+
+    #[test]
+    fn old_heartbeat_is_dropped() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let first = alice.seal_heartbeat(ping(1000.0)).unwrap();
+        let second = alice.seal_heartbeat(ping(3000.0)).unwrap();
+        assert!(open_heartbeat(&mut bob, second).is_ok());
+        assert_eq!(
+            open_heartbeat(&mut bob, first).err(),
+            Some(EngineError::ReplayedMessage)
+        );
+    }
+
+    #[test]
+    fn game_message_cannot_pass_as_heartbeat() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let sealed = alice.seal_heartbeat(player_click(1)).unwrap();
+        assert_eq!(
+            open_heartbeat(&mut bob, sealed).err(),
+            Some(EngineError::ForgedMessage)
+        );
+    }
+
+    #[test]
+    fn heartbeat_cannot_pass_as_sealed_message() {
+        let (mut alice, mut bob) = sessions("room", "room");
+        let sealed = match alice.seal_heartbeat(ping(1000.0)).unwrap() {
+            WsMessage::SealedHeartbeat {
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                msg,
+                mac,
+            } => WsMessage::Sealed {
+                ws_client_instance,
+                other_ws_client_instance,
+                sequence,
+                msg,
+                mac,
+            },
+            _ => panic!("not a sealed heartbeat"),
+        };
+        assert_eq!(
+            open(&mut bob, sealed).err(),
+            Some(EngineError::ForgedMessage)
+        );
+    }
+
+    #[test]
+    fn my_own_echo_is_dropped() {
+        let (mut alice, _bob) = sessions("room", "room");
+        let sealed = alice.seal(player_change(), 0.0).unwrap();
+        assert_eq!(
+            open(&mut alice, sealed).err(),
+            Some(EngineError::ForgedMessage)
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! `game_state.rs` - the state machine of one game session on the client.
+//! Every change of the state is a typed `GameEvent`. Only the transitions in the table are allowed.
+//! The machine does not execute anything: it returns the exit and entry actions
+//! and the client executes them in that order.
+
+///the game can be in various states and that differentiate the UI and actions
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameState {
+    ///the first screen: choose how to play
+    Start,
+    ///I asked to play and wait for the other player or the server
+    Asking,
+    ///the other player asked me to play
+    Asked,
+    ///the players flip cards
+    Play,
+    ///all the cards are face up
+    GameOver,
+    ///the quiz mini-game on this device
+    Quiz,
+}
+
+///everything that can change the game state, from a click or from a received message
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    ///click: ask the other player to play
+    AskToPlay,
+    ///click: play with the server rules
+    JoinServer,
+    ///click: play on this device
+    PlayHotSeat,
+    ///click: play alone on this device
+    PlaySolo,
+    ///message `WantToPlay` from the other player
+    WantToPlayReceived,
+    ///click: accept the invitation of the other player
+    AcceptPlay,
+    ///message `AcceptPlay` from the other player
+    AcceptPlayReceived,
+    ///message `AuthStart` from the server
+    ServerStarted,
+    ///message `AuthError` from the server: the game ended before all the cards matched
+    ServerEnded,
+    ///the last pair of cards matched
+    AllCardsMatched,
+    ///click: cancel my invitation
+    CancelInvite,
+    ///message `CancelInvite` from the player that asked me
+    CancelInviteReceived,
+    ///click: decline the invitation of the other player
+    DeclinePlay,
+    ///message `DeclinePlay` from the player I asked
+    DeclinePlayReceived,
+    ///nobody answered the invitation in time
+    InvitationExpired,
+    ///click: answer the invitation with my settings. Now I wait for the answer.
+    CounterPropose,
+    ///message `CounterPropose` from the player I asked. Now I must answer.
+    CounterProposalReceived,
+    ///the time limit of the game is over
+    TimeIsUp,
+    ///click: play the quiz
+    StartQuiz,
+    ///click: end the quiz
+    QuitQuiz,
+}
+
+///the actions the client executes when it exits or enters a state
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StateAction {
+    ///player1 begins and the time measurement starts
+    StartClock,
+    ///the time measurement stops
+    StopClock,
+    ///the game counts for the learning progress, also if it ended before all the pairs were found
+    FinishGame,
+    ///the result goes in the high scores. Only after all the pairs were found.
+    RecordHighScore,
+    ///the invitation waits for an answer only until the timeout
+    StartInvitationTimer,
+    ///the invitation got an answer or was cancelled
+    StopInvitationTimer,
+    ///the quiz starts from 0 with the first question
+    NewQuiz,
+}
+
+///an accepted event: the new state and the actions to execute
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Transition {
+    ///the state before the event
+    pub from: GameState,
+    ///the state after the event
+    pub to: GameState,
+    ///the exit actions of `from`, the entry actions of `to` and then the actions of the event
+    pub vec_actions: Vec<StateAction>,
+}
+
+///the event is not allowed in this state. Nothing changes.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IllegalTransition {
+    ///the current state
+    pub state: GameState,
+    ///the rejected event
+    pub event: GameEvent,
+}
+
+///the allowed transitions: from state, event, to state
+pub const TRANSITIONS: &[(GameState, GameEvent, GameState)] = &[
+    (GameState::Start, GameEvent::AskToPlay, GameState::Asking),
+    (GameState::Start, GameEvent::JoinServer, GameState::Asking),
+    (GameState::Start, GameEvent::PlayHotSeat, GameState::Play),
+    (GameState::Start, GameEvent::PlaySolo, GameState::Play),
+    (
+        GameState::Start,
+        GameEvent::WantToPlayReceived,
+        GameState::Asked,
+    ),
+    (GameState::Asked, GameEvent::AcceptPlay, GameState::Play),
+    (
+        GameState::Asking,
+        GameEvent::AcceptPlayReceived,
+        GameState::Play,
+    ),
+    (GameState::Asking, GameEvent::ServerStarted, GameState::Play),
+    (
+        GameState::Play,
+        GameEvent::AllCardsMatched,
+        GameState::GameOver,
+    ),
+    (GameState::Asking, GameEvent::CancelInvite, GameState::Start),
+    (
+        GameState::Asking,
+        GameEvent::DeclinePlayReceived,
+        GameState::Start,
+    ),
+    (
+        GameState::Asking,
+        GameEvent::InvitationExpired,
+        GameState::Start,
+    ),
+    (GameState::Asked, GameEvent::DeclinePlay, GameState::Start),
+    (
+        GameState::Asked,
+        GameEvent::CancelInviteReceived,
+        GameState::Start,
+    ),
+    (
+        GameState::Asked,
+        GameEvent::InvitationExpired,
+        GameState::Start,
+    ),
+    (
+        GameState::Asked,
+        GameEvent::CounterPropose,
+        GameState::Asking,
+    ),
+    (
+        GameState::Asking,
+        GameEvent::CounterProposalReceived,
+        GameState::Asked,
+    ),
+    (GameState::Play, GameEvent::TimeIsUp, GameState::GameOver),
+    (GameState::Play, GameEvent::ServerEnded, GameState::GameOver),
+    (GameState::Start, GameEvent::StartQuiz, GameState::Quiz),
+    (GameState::Quiz, GameEvent::QuitQuiz, GameState::Start),
+];
+
+impl std::fmt::Display for IllegalTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} is not allowed in the state {:?}",
+            self.event, self.state
+        )
+    }
+}
+
+impl GameState {
+    ///the state after the event or None if the event is not allowed
+    #[must_use]
+    pub fn next(self, event: GameEvent) -> Option<Self> {
+        TRANSITIONS
+            .iter()
+            .find(|(from, on, _to)| *from == self && *on == event)
+            .map(|(_from, _on, to)| *to)
+    }
+
+    ///check before the event changes anything
+    #[must_use]
+    pub fn accepts(self, event: GameEvent) -> bool {
+        self.next(event).is_some()
+    }
+
+    ///actions executed when the machine enters this state
+    #[must_use]
+    pub const fn on_entry(self) -> &'static [StateAction] {
+        match self {
+            Self::Asking | Self::Asked => &[StateAction::StartInvitationTimer],
+            Self::Play => &[StateAction::StartClock],
+            Self::GameOver => &[StateAction::FinishGame],
+            Self::Quiz => &[StateAction::NewQuiz],
+            Self::Start => &[],
+        }
+    }
+
+    ///actions executed when the machine exits this state
+    #[must_use]
+    pub const fn on_exit(self) -> &'static [StateAction] {
+        match self {
+            Self::Asking | Self::Asked => &[StateAction::StopInvitationTimer],
+            Self::Play => &[StateAction::StopClock],
+            Self::Start | Self::GameOver | Self::Quiz => &[],
+        }
+    }
+
+    ///apply the event
+    ///# Errors
+    ///Returns `IllegalTransition` if the event is not allowed in this state.
+    pub fn handle(self, event: GameEvent) -> Result<Transition, IllegalTransition> {
+        let to = self
+            .next(event)
+            .ok_or(IllegalTransition { state: self, event })?;
+        let mut vec_actions = self.on_exit().to_vec();
+        vec_actions.extend_from_slice(to.on_entry());
+        //a game ended by the time limit or by the server is not comparable with a finished game
+        if event == GameEvent::AllCardsMatched {
+            vec_actions.push(StateAction::RecordHighScore);
+        }
+        Ok(Transition {
+            from: self,
+            to,
+            vec_actions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_STATES: [GameState; 6] = [
+        GameState::Start,
+        GameState::Asking,
+        GameState::Asked,
+        GameState::Play,
+        GameState::GameOver,
+        GameState::Quiz,
+    ];
+
+    const ALL_EVENTS: [GameEvent; 20] = [
+        GameEvent::AskToPlay,
+        GameEvent::JoinServer,
+        GameEvent::PlayHotSeat,
+        GameEvent::PlaySolo,
+        GameEvent::WantToPlayReceived,
+        GameEvent::AcceptPlay,
+        GameEvent::AcceptPlayReceived,
+        GameEvent::ServerStarted,
+        GameEvent::ServerEnded,
+        GameEvent::AllCardsMatched,
+        GameEvent::CancelInvite,
+        GameEvent::CancelInviteReceived,
+        GameEvent::DeclinePlay,
+        GameEvent::DeclinePlayReceived,
+        GameEvent::InvitationExpired,
+        GameEvent::CounterPropose,
+        GameEvent::CounterProposalReceived,
+        GameEvent::TimeIsUp,
+        GameEvent::StartQuiz,
+        GameEvent::QuitQuiz,
+    ];
+
+    ///the expected result of every pair of state and event, written independently of `TRANSITIONS`
+    const fn expected(state: GameState, event: GameEvent) -> Option<GameState> {
+        match (state, event) {
+            (GameState::Start, GameEvent::AskToPlay | GameEvent::JoinServer) => {
+                Some(GameState::Asking)
+            }
+            (GameState::Start, GameEvent::PlayHotSeat | GameEvent::PlaySolo)
+            | (GameState::Asked, GameEvent::AcceptPlay)
+            | (GameState::Asking, GameEvent::AcceptPlayReceived | GameEvent::ServerStarted) => {
+                Some(GameState::Play)
+            }
+            (GameState::Start, GameEvent::WantToPlayReceived)
+            | (GameState::Asking, GameEvent::CounterProposalReceived) => Some(GameState::Asked),
+            (
+                GameState::Play,
+                GameEvent::AllCardsMatched | GameEvent::TimeIsUp | GameEvent::ServerEnded,
+            ) => Some(GameState::GameOver),
+            (GameState::Asked, GameEvent::CounterPropose) => Some(GameState::Asking),
+            (GameState::Start, GameEvent::StartQuiz) => Some(GameState::Quiz),
+            (
+                GameState::Asking,
+                GameEvent::CancelInvite
+                | GameEvent::DeclinePlayReceived
+                | GameEvent::InvitationExpired,
+            )
+            | (
+                GameState::Asked,
+                GameEvent::DeclinePlay
+                | GameEvent::CancelInviteReceived
+                | GameEvent::InvitationExpired,
+            )
+            | (GameState::Quiz, GameEvent::QuitQuiz) => Some(GameState::Start),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn every_state_and_event() {
+        for state in ALL_STATES {
+            for event in ALL_EVENTS {
+                assert_eq!(
+                    state.next(event),
+                    expected(state, event),
+                    "{state:?} {event:?}"
+                );
+                assert_eq!(state.accepts(event), expected(state, event).is_some());
+                match expected(state, event) {
+                    Some(to) => {
+                        let transition = state.handle(event).unwrap();
+                        assert_eq!(transition.from, state);
+                        assert_eq!(transition.to, to);
+                    }
+                    None => {
+                        assert_eq!(state.handle(event), Err(IllegalTransition { state, event }));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn table_has_no_duplicates() {
+        for (index, (from, on, _to)) in TRANSITIONS.iter().enumerate() {
+            assert!(TRANSITIONS
+                .iter()
+                .skip(index + 1)
+                .all(|(other_from, other_on, _)| other_from != from || other_on != on));
+        }
+    }
+
+    #[test]
+    fn accept_play_is_rejected_in_play() {
+        assert_eq!(
+            GameState::Play.handle(GameEvent::AcceptPlayReceived),
+            Err(IllegalTransition {
+                state: GameState::Play,
+                event: GameEvent::AcceptPlayReceived,
+            })
+        );
+    }
+
+    #[test]
+    fn game_over_is_final() {
+        for event in ALL_EVENTS {
+            assert!(!GameState::GameOver.accepts(event));
+        }
+    }
+
+    #[test]
+    fn entering_play_starts_the_clock() {
+        for event in [GameEvent::PlayHotSeat, GameEvent::PlaySolo] {
+            assert_eq!(
+                GameState::Start.handle(event).unwrap().vec_actions,
+                vec![StateAction::StartClock]
+            );
+        }
+        for (from, event) in [
+            (GameState::Asked, GameEvent::AcceptPlay),
+            (GameState::Asking, GameEvent::AcceptPlayReceived),
+            (GameState::Asking, GameEvent::ServerStarted),
+        ] {
+            assert_eq!(
+                from.handle(event).unwrap().vec_actions,
+                vec![StateAction::StopInvitationTimer, StateAction::StartClock]
+            );
+        }
+    }
+
+    #[test]
+    fn game_over_stops_the_clock_before_recording() {
+        assert_eq!(
+            GameState::Play
+                .handle(GameEvent::AllCardsMatched)
+                .unwrap()
+                .vec_actions,
+            vec![
+                StateAction::StopClock,
+                StateAction::FinishGame,
+                StateAction::RecordHighScore
+            ]
+        );
+    }
+
+    #[test]
+    fn invitation_starts_the_timer() {
+        for (from, event) in [
+            (GameState::Start, GameEvent::AskToPlay),
+            (GameState::Start, GameEvent::JoinServer),
+            (GameState::Start, GameEvent::WantToPlayReceived),
+        ] {
+            assert_eq!(
+                from.handle(event).unwrap().vec_actions,
+                vec![StateAction::StartInvitationTimer]
+            );
+        }
+    }
+
+    #[test]
+    fn invitation_end_returns_to_start() {
+        for (from, event) in [
+            (GameState::Asking, GameEvent::CancelInvite),
+            (GameState::Asking, GameEvent::DeclinePlayReceived),
+            (GameState::Asking, GameEvent::InvitationExpired),
+            (GameState::Asked, GameEvent::DeclinePlay),
+            (GameState::Asked, GameEvent::CancelInviteReceived),
+            (GameState::Asked, GameEvent::InvitationExpired),
+        ] {
+            let transition = from.handle(event).unwrap();
+            assert_eq!(transition.to, GameState::Start);
+            assert_eq!(
+                transition.vec_actions,
+                vec![StateAction::StopInvitationTimer]
+            );
+        }
+    }
+
+    #[test]
+    fn counter_proposal_swaps_the_roles_and_restarts_the_timer() {
+        for (from, event, to) in [
+            (
+                GameState::Asked,
+                GameEvent::CounterPropose,
+                GameState::Asking,
+            ),
+            (
+                GameState::Asking,
+                GameEvent::CounterProposalReceived,
+                GameState::Asked,
+            ),
+        ] {
+            let transition = from.handle(event).unwrap();
+            assert_eq!(transition.to, to);
+            assert_eq!(
+                transition.vec_actions,
+                vec![
+                    StateAction::StopInvitationTimer,
+                    StateAction::StartInvitationTimer
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn game_over_without_all_pairs_is_not_recorded() {
+        for event in [GameEvent::TimeIsUp, GameEvent::ServerEnded] {
+            let transition = GameState::Play.handle(event).unwrap();
+            assert_eq!(transition.to, GameState::GameOver);
+            assert_eq!(
+                transition.vec_actions,
+                vec![StateAction::StopClock, StateAction::FinishGame]
+            );
+        }
+    }
+
+    #[test]
+    fn quiz_starts_new_and_returns_to_start() {
+        assert_eq!(
+            GameState::Start
+                .handle(GameEvent::StartQuiz)
+                .unwrap()
+                .vec_actions,
+            vec![StateAction::NewQuiz]
+        );
+        assert_eq!(
+            GameState::Quiz.handle(GameEvent::QuitQuiz).unwrap().to,
+            GameState::Start
+        );
+        assert!(!GameState::Quiz.accepts(GameEvent::WantToPlayReceived));
+    }
+
+    #[test]
+    fn invitation_events_are_rejected_in_play() {
+        for event in [
+            GameEvent::CancelInvite,
+            GameEvent::CancelInviteReceived,
+            GameEvent::DeclinePlay,
+            GameEvent::DeclinePlayReceived,
+            GameEvent::InvitationExpired,
+            GameEvent::CounterPropose,
+            GameEvent::CounterProposalReceived,
+        ] {
+            assert!(!GameState::Play.accepts(event));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! `heartbeat.rs` - is the other player still there?
+//! In a game with another machine, every 2 seconds a `Ping` goes to the other player.
+//! He answers with a `Pong` that returns the time of the `Ping`. That is the round-trip latency.
+//! Every `Ping` or `Pong` from him means he is there.
+//! Without news for a while he is lagging. After the disconnect timeout he is gone.
+//! The disconnect timeout is configurable with the url query `?disconnect_timeout=30` in seconds.
+
+use crate::query::query_value;
+
+///a ping every 2 seconds
+pub const PING_INTERVAL_MS: f64 = 2_000.0;
+///without news for 5 seconds the other player is lagging
+const LAGGING_SILENCE_MS: f64 = 5_000.0;
+///a round-trip over 1 second is lagging
+const LAGGING_RTT_MS: f64 = 1_000.0;
+///without news for 20 seconds the other player is gone, if the url query does not say otherwise
+const DEFAULT_DISCONNECT_TIMEOUT_MS: f64 = 20_000.0;
+
+///what we know about the other player
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PeerPresence {
+    ///he answers quickly
+    Online,
+    ///he answers slowly or not for a few seconds
+    Lagging,
+    ///no news for longer than the disconnect timeout
+    Gone,
+}
+
+///the heartbeat with the other player
+#[derive(Debug)]
+pub struct Heartbeat {
+    ///time of my last `Ping` from js `Date.now()`
+    pub last_ping_sent: f64,
+    ///time of the last `Ping` or `Pong` from the other player
+    pub last_heard: f64,
+    ///the last measured round-trip latency
+    pub rtt_ms: Option<f64>,
+    ///no news for longer than this and the other player is gone
+    pub disconnect_timeout_ms: f64,
+}
+
+impl Heartbeat {
+    ///the disconnect timeout from the url query `?disconnect_timeout=` in seconds
+    #[must_use]
+    pub fn from_location_search(search: &str) -> Self {
+        let disconnect_timeout_ms = query_value(search, "disconnect_timeout")
+            .and_then(|seconds| seconds.parse::<f64>().ok())
+            .filter(|seconds| *seconds > 0.0)
+            .map_or(DEFAULT_DISCONNECT_TIMEOUT_MS, |seconds| seconds * 1000.0);
+        Self {
+            last_ping_sent: 0.0,
+            last_heard: 0.0,
+            rtt_ms: None,
+            disconnect_timeout_ms,
+        }
+    }
+
+    ///a new game starts with a fresh heartbeat
+    pub const fn reset(&mut self, now: f64) {
+        self.last_ping_sent = 0.0;
+        self.last_heard = now;
+        self.rtt_ms = None;
+    }
+
+    ///is it time for the next ping
+    #[must_use]
+    pub fn is_ping_due(&self, now: f64) -> bool {
+        now - self.last_ping_sent >= PING_INTERVAL_MS
+    }
+
+    ///a `Ping` from the other player
+    pub const fn on_ping(&mut self, now: f64) {
+        self.last_heard = now;
+    }
+
+    ///a `Pong` from the other player with the time of my `Ping`
+    pub fn on_pong(&mut self, ping_sent_at: f64, now: f64) {
+        self.last_heard = now;
+        self.rtt_ms = Some(now - ping_sent_at);
+    }
+
+    ///the short text in the score bar
+    #[must_use]
+    pub fn presence_text(&self, now: f64) -> String {
+        match (self.presence(now), self.rtt_ms) {
+            (PeerPresence::Online, Some(rtt_ms)) => format!("online {rtt_ms:.0} ms"),
+            (PeerPresence::Online, None) => "online".to_string(),
+            (PeerPresence::Lagging, Some(rtt_ms)) => format!("lagging {rtt_ms:.0} ms"),
+            (PeerPresence::Lagging, None) => "lagging".to_string(),
+            (PeerPresence::Gone, _) => "gone".to_string(),
+        }
+    }
+
+    ///online, lagging or gone
+    #[must_use]
+    pub fn presence(&self, now: f64) -> PeerPresence {
+        let silence_ms = now - self.last_heard;
+        if silence_ms >= self.disconnect_timeout_ms {
+            PeerPresence::Gone
+        } else if silence_ms >= LAGGING_SILENCE_MS
+            || self.rtt_ms.is_some_and(|rtt_ms| rtt_ms >= LAGGING_RTT_MS)
+        {
+            PeerPresence::Lagging
+        } else {
+            PeerPresence::Online
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(now: f64) -> Heartbeat {
+        let mut heartbeat = Heartbeat::from_location_search("");
+        heartbeat.reset(now);
+        heartbeat
+    }
+
+    #[test]
+    fn quick_answers_are_online() {
+        let mut heartbeat = heartbeat(1000.0);
+        assert_eq!(heartbeat.presence(1000.0), PeerPresence::Online);
+        heartbeat.on_pong(2000.0, 2200.0);
+        assert_eq!(heartbeat.presence(2200.0), PeerPresence::Online);
+        assert_eq!(heartbeat.presence_text(2200.0), "online 200 ms");
+    }
+
+    #[test]
+    fn silence_or_slow_answers_are_lagging() {
+        let mut heartbeat = heartbeat(1000.0);
+        assert_eq!(heartbeat.presence(5999.0), PeerPresence::Online);
+        assert_eq!(heartbeat.presence(6000.0), PeerPresence::Lagging);
+        //a Ping is news from him
+        heartbeat.on_ping(6000.0);
+        assert_eq!(heartbeat.presence(6000.0), PeerPresence::Online);
+        heartbeat.on_pong(6000.0, 7000.0);
+        assert_eq!(heartbeat.presence(7000.0), PeerPresence::Lagging);
+    }
+
+    #[test]
+    fn long_silence_is_gone() {
+        let heartbeat = heartbeat(1000.0);
+        assert_eq!(heartbeat.presence(20_999.0), PeerPresence::Lagging);
+        assert_eq!(heartbeat.presence(21_000.0), PeerPresence::Gone);
+        assert_eq!(heartbeat.presence_text(21_000.0), "gone");
+    }
+
+    #[test]
+    fn disconnect_timeout_from_the_url_query() {
+        let heartbeat = Heartbeat::from_location_search("?room=a&disconnect_timeout=30");
+        assert!((heartbeat.disconnect_timeout_ms - 30_000.0).abs() < f64::EPSILON);
+        //a similar name, a wrong or a negative value keep the default
+        for search in &[
+            "?my_disconnect_timeout=30",
+            "?disconnect_timeout=abc",
+            "?disconnect_timeout=-5",
+        ] {
+            let heartbeat = Heartbeat::from_location_search(search);
+            assert!(
+                (heartbeat.disconnect_timeout_ms - DEFAULT_DISCONNECT_TIMEOUT_MS).abs()
+                    < f64::EPSILON
+            );
+        }
+    }
+
+    #[test]
+    fn ping_every_interval() {
+        let mut heartbeat = heartbeat(1000.0);
+        assert!(heartbeat.is_ping_due(2000.0));
+        heartbeat.last_ping_sent = 2000.0;
+        assert!(!heartbeat.is_ping_due(3999.0));
+        assert!(heartbeat.is_ping_due(4000.0));
+    }
+}
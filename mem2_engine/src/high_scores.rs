@@ -0,0 +1,284 @@
+//! `high_scores.rs` - the ranking of the best finished games.
+//!
+//! Results are comparable only for the same game mode, grid size, deck and time limit. That is the key of one table.
+//! The best result has the fewest clicks. If the clicks are equal, the shorter time wins.
+//! Two players on one device have their own clicks and points, so one game gives an entry for each of them.
+//! The client saves the tables in localStorage and exports and imports them as json.
+
+///only the best results are worth to remember
+pub const HIGH_SCORES_MAX_PER_TABLE: usize = 10;
+
+///results are comparable only inside the same key
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct HighScoreKey {
+    ///game mode as string from `GameMode.as_ref()`
+    pub game_mode: String,
+    ///grid size like 4x4
+    pub grid_size: String,
+    ///deck is the content folder name
+    pub deck: String,
+    ///the time limit of the game in seconds. 0 is without a limit, also in the tables saved before it.
+    #[serde(default)]
+    pub time_limit_s: u32,
+}
+
+///one finished game
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    ///counts only clicks that flip the card
+    pub count_all_clicks: usize,
+    ///milliseconds from the start to the end of the game
+    pub duration_ms: f64,
+    ///points of this player at the end of the game
+    pub points: usize,
+    ///date and time in ISO format
+    pub date: String,
+    ///player 1 or 2 of a game with two players. 0 alone and in the tables saved before it.
+    #[serde(default)]
+    pub player_number: usize,
+}
+
+///all the entries for one key
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreTable {
+    ///key of the table
+    pub key: HighScoreKey,
+    ///sorted from the best to the worst
+    pub vec_entries: Vec<HighScoreEntry>,
+}
+
+///all the tables
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct HighScores {
+    ///one table for every key
+    pub vec_tables: Vec<HighScoreTable>,
+}
+
+impl HighScoreEntry {
+    ///fewer clicks is better, then shorter time is better
+    fn is_better_than(&self, other: &Self) -> bool {
+        self.count_all_clicks < other.count_all_clicks
+            || (self.count_all_clicks == other.count_all_clicks
+                && self.duration_ms < other.duration_ms)
+    }
+}
+
+impl HighScores {
+    ///the entries of one table. Empty if the table does not exist yet.
+    #[must_use]
+    pub fn entries(&self, key: &HighScoreKey) -> &[HighScoreEntry] {
+        self.vec_tables
+            .iter()
+            .find(|table| table.key == *key)
+            .map_or(&[], |table| table.vec_entries.as_slice())
+    }
+
+    ///insert the entry in the right place and return the rank (0 based).
+    ///
+    ///Returns None if the entry is not good enough to be in the table.
+    pub fn insert(&mut self, key: &HighScoreKey, entry: HighScoreEntry) -> Option<usize> {
+        let position =
+            if let Some(position) = self.vec_tables.iter().position(|table| table.key == *key) {
+                position
+            } else {
+                self.vec_tables.push(HighScoreTable {
+                    key: key.clone(),
+                    vec_entries: Vec::new(),
+                });
+                self.vec_tables.len() - 1
+            };
+        let vec_entries = &mut self.vec_tables.get_mut(position)?.vec_entries;
+
+        let rank = vec_entries
+            .iter()
+            .position(|old| entry.is_better_than(old))
+            .unwrap_or(vec_entries.len());
+        if rank >= HIGH_SCORES_MAX_PER_TABLE {
+            return None;
+        }
+        vec_entries.insert(rank, entry);
+        vec_entries.truncate(HIGH_SCORES_MAX_PER_TABLE);
+        Some(rank)
+    }
+
+    ///insert the entries of one game and return the ranks (0 based) they have after all of them are inserted.
+    ///
+    ///An entry pushed out of the table by a later one has no rank.
+    pub fn insert_all(
+        &mut self,
+        key: &HighScoreKey,
+        vec_entries: Vec<HighScoreEntry>,
+    ) -> Vec<usize> {
+        let mut vec_ranks: Vec<usize> = Vec::new();
+        for entry in vec_entries {
+            if let Some(rank) = self.insert(key, entry) {
+                //the entries after the new one move down
+                for old_rank in &mut vec_ranks {
+                    if *old_rank >= rank {
+                        *old_rank += 1;
+                    }
+                }
+                vec_ranks.retain(|old_rank| *old_rank < HIGH_SCORES_MAX_PER_TABLE);
+                vec_ranks.push(rank);
+            }
+        }
+        vec_ranks
+    }
+
+    ///merge the imported tables with the existing entries
+    pub fn merge(&mut self, imported: Self) {
+        for table in imported.vec_tables {
+            for entry in table.vec_entries {
+                //duplicates are not imported twice
+                let is_duplicate = self.entries(&table.key).iter().any(|old| {
+                    old.date == entry.date
+                        && old.count_all_clicks == entry.count_all_clicks
+                        && old.player_number == entry.player_number
+                });
+                if !is_duplicate {
+                    self.insert(&table.key, entry);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time_limit_s: u32) -> HighScoreKey {
+        HighScoreKey {
+            game_mode: "HotSeat".to_string(),
+            grid_size: "4x4".to_string(),
+            deck: "content".to_string(),
+            time_limit_s,
+        }
+    }
+
+    fn entry(count_all_clicks: usize, duration_ms: f64, date: &str) -> HighScoreEntry {
+        HighScoreEntry {
+            count_all_clicks,
+            duration_ms,
+            points: 8,
+            date: date.to_string(),
+            player_number: 0,
+        }
+    }
+
+    ///the clicks of the entries in the order of the table
+    fn clicks(high_scores: &HighScores, key: &HighScoreKey) -> Vec<usize> {
+        high_scores
+            .entries(key)
+            .iter()
+            .map(|entry| entry.count_all_clicks)
+            .collect()
+    }
+
+    #[test]
+    fn fewer_clicks_rank_higher() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.insert(&key(0), entry(30, 9000.0, "a")), Some(0));
+        assert_eq!(high_scores.insert(&key(0), entry(20, 9000.0, "b")), Some(0));
+        assert_eq!(high_scores.insert(&key(0), entry(25, 9000.0, "c")), Some(1));
+        assert_eq!(clicks(&high_scores, &key(0)), vec![20, 25, 30]);
+    }
+
+    #[test]
+    fn shorter_time_wins_with_equal_clicks() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(&key(0), entry(20, 9000.0, "a"));
+        assert_eq!(high_scores.insert(&key(0), entry(20, 8000.0, "b")), Some(0));
+        //a tie is not better, it goes after the older entry
+        assert_eq!(high_scores.insert(&key(0), entry(20, 8000.0, "c")), Some(1));
+        assert_eq!(high_scores.entries(&key(0))[0].date, "b");
+    }
+
+    #[test]
+    fn table_keeps_only_the_best() {
+        let mut high_scores = HighScores::default();
+        for clicks in 20..20 + HIGH_SCORES_MAX_PER_TABLE {
+            high_scores.insert(&key(0), entry(clicks, 9000.0, "a"));
+        }
+        //worse than all is not in the table
+        assert_eq!(high_scores.insert(&key(0), entry(99, 9000.0, "b")), None);
+        //better pushes out the worst
+        assert_eq!(high_scores.insert(&key(0), entry(10, 9000.0, "c")), Some(0));
+        let vec_clicks = clicks(&high_scores, &key(0));
+        assert_eq!(vec_clicks.len(), HIGH_SCORES_MAX_PER_TABLE);
+        assert_eq!(
+            vec_clicks.last(),
+            Some(&(20 + HIGH_SCORES_MAX_PER_TABLE - 2))
+        );
+    }
+
+    #[test]
+    fn timed_games_have_their_own_table() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(&key(0), entry(30, 9000.0, "a"));
+        high_scores.insert(&key(60), entry(40, 9000.0, "b"));
+        assert_eq!(clicks(&high_scores, &key(0)), vec![30]);
+        assert_eq!(clicks(&high_scores, &key(60)), vec![40]);
+        assert!(high_scores.entries(&key(120)).is_empty());
+    }
+
+    #[test]
+    fn both_players_of_one_game_are_ranked() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(&key(0), entry(25, 9000.0, "a"));
+        let vec_ranks = high_scores.insert_all(
+            &key(0),
+            vec![entry(30, 9000.0, "b"), entry(20, 9000.0, "b")],
+        );
+        //the second entry is better and moves the first one down
+        assert_eq!(vec_ranks, vec![2, 0]);
+        assert_eq!(clicks(&high_scores, &key(0)), vec![20, 25, 30]);
+    }
+
+    #[test]
+    fn pushed_out_entry_has_no_rank() {
+        let mut high_scores = HighScores::default();
+        for clicks in 20..20 + HIGH_SCORES_MAX_PER_TABLE - 1 {
+            high_scores.insert(&key(0), entry(clicks, 9000.0, "a"));
+        }
+        let vec_ranks = high_scores.insert_all(
+            &key(0),
+            vec![entry(90, 9000.0, "b"), entry(10, 9000.0, "b")],
+        );
+        assert_eq!(vec_ranks, vec![0]);
+    }
+
+    #[test]
+    fn merge_keeps_both_players_of_one_game() {
+        let mut player1 = entry(30, 9000.0, "a");
+        player1.player_number = 1;
+        let mut player2 = entry(30, 9000.0, "a");
+        player2.player_number = 2;
+        let mut imported = HighScores::default();
+        imported.insert_all(&key(0), vec![player1, player2]);
+        let mut high_scores = HighScores::default();
+        high_scores.merge(imported);
+        assert_eq!(clicks(&high_scores, &key(0)), vec![30, 30]);
+    }
+
+    #[test]
+    fn merge_skips_the_duplicates() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(&key(0), entry(30, 9000.0, "a"));
+        let mut imported = HighScores::default();
+        imported.insert(&key(0), entry(30, 9000.0, "a"));
+        imported.insert(&key(0), entry(25, 9000.0, "b"));
+        imported.insert(&key(60), entry(40, 9000.0, "c"));
+        high_scores.merge(imported);
+        assert_eq!(clicks(&high_scores, &key(0)), vec![25, 30]);
+        assert_eq!(clicks(&high_scores, &key(60)), vec![40]);
+    }
+
+    #[test]
+    fn tables_saved_before_the_time_limit_are_without_a_limit() {
+        let json = r#"{"vec_tables":[{"key":{"game_mode":"HotSeat","grid_size":"4x4","deck":"content"},
+            "vec_entries":[{"count_all_clicks":30,"duration_ms":9000.0,"points":8,"date":"a"}]}]}"#;
+        let high_scores: HighScores = serde_json::from_str(json).unwrap();
+        assert_eq!(clicks(&high_scores, &key(0)), vec![30]);
+    }
+}
@@ -0,0 +1,86 @@
+//! `keyboard.rs` - the focus moves across the card grid with the arrow keys, Home and End.
+//!
+//! The card indexes start at 1 and go row by row. The last row can be shorter than the others.
+//! The focus stops at the edges of the grid and does not wrap to the next row.
+
+///the card index after the key.
+///
+///None for other keys and at the edge of the grid.
+///End on the last row that is not full goes to its last card.
+#[must_use]
+pub fn next_card_index(
+    card_index: usize,
+    key: &str,
+    grid_columns: usize,
+    card_count: usize,
+) -> Option<usize> {
+    if grid_columns == 0 || card_index == 0 || card_index > card_count {
+        return None;
+    }
+    let column = (card_index - 1) % grid_columns;
+    let next = match key {
+        "ArrowLeft" if column > 0 => card_index - 1,
+        "ArrowRight" if column + 1 < grid_columns => card_index + 1,
+        "ArrowUp" if card_index > grid_columns => card_index - grid_columns,
+        "ArrowDown" => card_index + grid_columns,
+        "Home" => card_index - column,
+        "End" => (card_index - column + grid_columns - 1).min(card_count),
+        _ => return None,
+    };
+    if next <= card_count {
+        Some(next)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a grid of 4 columns with 10 cards:
+    // 1  2  3  4
+    // 5  6  7  8
+    // 9 10
+
+    #[test]
+    fn arrows_move_inside_the_grid() {
+        assert_eq!(next_card_index(6, "ArrowLeft", 4, 10), Some(5));
+        assert_eq!(next_card_index(6, "ArrowRight", 4, 10), Some(7));
+        assert_eq!(next_card_index(6, "ArrowUp", 4, 10), Some(2));
+        assert_eq!(next_card_index(6, "ArrowDown", 4, 10), Some(10));
+    }
+
+    #[test]
+    fn arrows_stop_at_the_edges() {
+        assert_eq!(next_card_index(5, "ArrowLeft", 4, 10), None);
+        assert_eq!(next_card_index(4, "ArrowRight", 4, 10), None);
+        assert_eq!(next_card_index(3, "ArrowUp", 4, 10), None);
+        assert_eq!(next_card_index(10, "ArrowDown", 4, 10), None);
+        //below the card 7 there is no card in the last row
+        assert_eq!(next_card_index(7, "ArrowDown", 4, 10), None);
+        //the last card of a short row has no card on its right
+        assert_eq!(next_card_index(10, "ArrowRight", 4, 10), None);
+    }
+
+    #[test]
+    fn home_and_end_go_to_the_ends_of_the_row() {
+        assert_eq!(next_card_index(7, "Home", 4, 10), Some(5));
+        assert_eq!(next_card_index(7, "End", 4, 10), Some(8));
+        assert_eq!(next_card_index(9, "Home", 4, 10), Some(9));
+    }
+
+    #[test]
+    fn end_on_the_short_last_row_goes_to_the_last_card() {
+        assert_eq!(next_card_index(9, "End", 4, 10), Some(10));
+        assert_eq!(next_card_index(10, "End", 4, 10), Some(10));
+    }
+
+    #[test]
+    fn other_keys_and_invalid_grids_do_nothing() {
+        assert_eq!(next_card_index(6, "Enter", 4, 10), None);
+        assert_eq!(next_card_index(6, "ArrowLeft", 0, 10), None);
+        assert_eq!(next_card_index(0, "ArrowRight", 4, 10), None);
+        assert_eq!(next_card_index(11, "ArrowLeft", 4, 10), None);
+    }
+}
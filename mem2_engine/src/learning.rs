@@ -0,0 +1,247 @@
+//! `learning.rs` - what the child knows about every letter.
+//!
+//! A pair is found quickly if the cards of its letter were flipped at most 3 times in the game:
+//! the player remembered where the first card was. More flips count as a miss.
+//! The scheduler is a Leitner system. Every letter is in one of 5 boxes.
+//! A quick pair moves the letter one box up, a miss moves it back to the first box.
+//! A letter in a higher box comes back after more games: 0, 1, 2, 4 or 8 games.
+//! The new game chooses the letters due for repetition more often, the known letters less often.
+
+use crate::CARD_NUMBER_MAX;
+use rand::Rng;
+
+///a pair found with so many flips of its letter or less is found quickly
+pub const QUICK_FLIPS: usize = 3;
+///the highest Leitner box: the letter is known
+pub const BOX_MAX: u8 = 4;
+///after how many games the letter in the box is due again
+const BOX_INTERVAL_GAMES: [u32; BOX_MAX as usize + 1] = [0, 1, 2, 4, 8];
+///the chance of a letter not seen yet
+const WEIGHT_NEW: u32 = 3;
+///the chance of a letter that is not due. It is never 0, so every letter can be chosen.
+const WEIGHT_NOT_DUE: u32 = 1;
+
+///the statistics and the Leitner box of one letter
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct LetterProgress {
+    ///pairs found with at most `QUICK_FLIPS` flips
+    pub found_quickly: u32,
+    ///pairs found only after more flips
+    pub missed: u32,
+    ///from 0 to `BOX_MAX`
+    pub box_level: u8,
+    ///the game count when the pair was last found
+    pub last_game: u32,
+}
+
+impl LetterProgress {
+    ///the letter was never found
+    #[must_use]
+    pub const fn is_new(&self) -> bool {
+        self.found_quickly == 0 && self.missed == 0
+    }
+
+    ///the letter should be repeated in this game
+    #[must_use]
+    pub fn is_due(&self, game_count: u32) -> bool {
+        let interval = BOX_INTERVAL_GAMES
+            .get(usize::from(self.box_level))
+            .copied()
+            .unwrap_or(0);
+        game_count >= self.last_game + interval
+    }
+
+    ///from 0 to 100 percent by the Leitner box
+    #[must_use]
+    pub fn mastery_percent(&self) -> u32 {
+        u32::from(self.box_level.min(BOX_MAX)) * 100 / u32::from(BOX_MAX)
+    }
+
+    ///the chance of the letter to be in the next game
+    #[must_use]
+    pub fn weight(&self, game_count: u32) -> u32 {
+        if self.is_new() {
+            WEIGHT_NEW
+        } else if self.is_due(game_count) {
+            //the letters in the low boxes are the hard ones
+            2 + 2 * u32::from(BOX_MAX.saturating_sub(self.box_level))
+        } else {
+            WEIGHT_NOT_DUE
+        }
+    }
+}
+
+///the progress of all the letters. It is saved on the device of the child.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct LearningProgress {
+    ///the finished games
+    pub game_count: u32,
+    ///index is the card number. Index 0 is the card face down and is not used.
+    pub vec_letters: Vec<LetterProgress>,
+}
+
+impl Default for LearningProgress {
+    fn default() -> Self {
+        Self {
+            game_count: 0,
+            vec_letters: vec![LetterProgress::default(); CARD_NUMBER_MAX + 1],
+        }
+    }
+}
+
+impl LearningProgress {
+    ///the progress of the card number
+    #[must_use]
+    pub fn letter(&self, card_number: usize) -> LetterProgress {
+        self.vec_letters
+            .get(card_number)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    ///the pair of the card number was found after so many flips of its cards
+    pub fn record_pair(&mut self, card_number: usize, flips: usize) {
+        if card_number == 0 || card_number > CARD_NUMBER_MAX {
+            return;
+        }
+        //an old saved progress can have fewer letters
+        if self.vec_letters.len() <= card_number {
+            self.vec_letters
+                .resize(CARD_NUMBER_MAX + 1, LetterProgress::default());
+        }
+        let game_count = self.game_count;
+        if let Some(letter) = self.vec_letters.get_mut(card_number) {
+            if flips <= QUICK_FLIPS {
+                letter.found_quickly += 1;
+                letter.box_level = (letter.box_level + 1).min(BOX_MAX);
+            } else {
+                letter.missed += 1;
+                letter.box_level = 0;
+            }
+            letter.last_game = game_count;
+        }
+    }
+
+    ///one more game is finished
+    pub const fn finish_game(&mut self) {
+        self.game_count += 1;
+    }
+
+    ///distinct card numbers for the new game. The hard and due letters have more chance.
+    pub fn choose_letters<R: Rng>(&self, card_pairs: usize, rng: &mut R) -> Vec<usize> {
+        let mut vec_candidates: Vec<(usize, u32)> = (1..=CARD_NUMBER_MAX)
+            .map(|card_number| {
+                (
+                    card_number,
+                    self.letter(card_number).weight(self.game_count),
+                )
+            })
+            .collect();
+        let mut vec_chosen = Vec::new();
+        while vec_chosen.len() < card_pairs.min(CARD_NUMBER_MAX) {
+            let total: u32 = vec_candidates.iter().map(|(_, weight)| weight).sum();
+            let mut ticket = rng.gen_range(0, total);
+            let position = vec_candidates
+                .iter()
+                .position(|(_, weight)| {
+                    if ticket < *weight {
+                        true
+                    } else {
+                        ticket -= weight;
+                        false
+                    }
+                })
+                .unwrap_or(0);
+            vec_chosen.push(vec_candidates.remove(position).0);
+        }
+        vec_chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn quick_pair_moves_the_letter_up() {
+        let mut progress = LearningProgress::default();
+        progress.record_pair(1, 2);
+        progress.record_pair(1, QUICK_FLIPS);
+        assert_eq!(progress.letter(1).box_level, 2);
+        assert_eq!(progress.letter(1).found_quickly, 2);
+        assert_eq!(progress.letter(1).mastery_percent(), 50);
+    }
+
+    #[test]
+    fn miss_moves_the_letter_back_to_the_first_box() {
+        let mut progress = LearningProgress::default();
+        progress.record_pair(2, 2);
+        progress.record_pair(2, 2);
+        progress.record_pair(2, QUICK_FLIPS + 1);
+        assert_eq!(progress.letter(2).box_level, 0);
+        assert_eq!(progress.letter(2).missed, 1);
+    }
+
+    #[test]
+    fn higher_boxes_wait_longer() {
+        let mut progress = LearningProgress::default();
+        progress.record_pair(3, 2);
+        progress.record_pair(3, 2);
+        //box 2 waits 2 games
+        assert!(!progress.letter(3).is_due(1));
+        assert!(progress.letter(3).is_due(2));
+        progress.record_pair(4, 9);
+        assert!(progress.letter(4).is_due(0));
+    }
+
+    #[test]
+    fn invalid_card_numbers_are_ignored() {
+        let mut progress = LearningProgress::default();
+        progress.record_pair(0, 2);
+        progress.record_pair(CARD_NUMBER_MAX + 1, 2);
+        assert_eq!(progress, LearningProgress::default());
+    }
+
+    #[test]
+    fn old_progress_with_fewer_letters_grows() {
+        let mut progress = LearningProgress {
+            game_count: 0,
+            vec_letters: Vec::new(),
+        };
+        progress.record_pair(26, 2);
+        assert_eq!(progress.letter(26).found_quickly, 1);
+    }
+
+    #[test]
+    fn chosen_letters_are_distinct() {
+        let progress = LearningProgress::default();
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut vec_chosen = progress.choose_letters(18, &mut rng);
+        assert_eq!(vec_chosen.len(), 18);
+        vec_chosen.sort_unstable();
+        vec_chosen.dedup();
+        assert_eq!(vec_chosen.len(), 18);
+        assert!(vec_chosen.iter().all(|n| *n >= 1 && *n <= CARD_NUMBER_MAX));
+    }
+
+    #[test]
+    fn hard_letters_are_chosen_more_often() {
+        let mut progress = LearningProgress::default();
+        //every letter is known except A, that was just missed
+        for card_number in 2..=CARD_NUMBER_MAX {
+            for _ in 0..BOX_MAX {
+                progress.record_pair(card_number, 2);
+            }
+        }
+        progress.record_pair(1, 10);
+        progress.finish_game();
+        let mut rng = SmallRng::seed_from_u64(11);
+        let count_a = (0..100)
+            .filter(|_| progress.choose_letters(1, &mut rng) == vec![1])
+            .count();
+        //A has the weight 10 against 25 letters with the weight 1
+        assert!(count_a > 20, "{}", count_a);
+    }
+}
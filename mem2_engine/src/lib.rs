@@ -0,0 +1,567 @@
+//! `mem2_engine` - the part of mem2 that is shared by the game client (wasm) and the server (native).
+//! It has the cards, the rules of the memory game, the game settings, the game state machine, the WebSocket messages
+//! and their authentication.
+//! The `morse` module encodes any text to Morse code and times its dots and dashes.
+//! The `animation` module times the card animations that the client renders as CSS.
+//! The `heartbeat` module tells if the other player is online, lagging or gone.
+//! The `lobby` module keeps the list of the players online.
+//! It knows nothing about HTML, Virtual Dom or WebSocket connections.
+//! In the server-authoritative mode the server owns the `GameEngine` and the clients see only the `GameView`.
+//! A card face down in the `GameView` has the card number 0, so the client cannot know it before it is flipped.
+
+//region: Clippy
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(
+    //library from dependencies have this clippy warnings. Not my code.
+    clippy::cargo_common_metadata,
+    clippy::multiple_crate_versions,
+)]
+//endregion
+
+//region: extern and use statements
+#[macro_use]
+extern crate serde_derive;
+
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::FromEntropy;
+use rand::Rng;
+
+mod animation;
+mod auth;
+mod game_state;
+mod heartbeat;
+mod high_scores;
+mod keyboard;
+mod learning;
+mod lobby;
+pub mod morse;
+mod query;
+mod quiz;
+mod settings;
+mod ws_message;
+pub use animation::{CardAnimation, CardAnimations};
+pub use auth::{new_room_secret, PeerSession, RoomKey, SessionKey};
+pub use game_state::{
+    GameEvent, GameState, IllegalTransition, StateAction, Transition, TRANSITIONS,
+};
+pub use heartbeat::{Heartbeat, PeerPresence, PING_INTERVAL_MS};
+pub use high_scores::{
+    HighScoreEntry, HighScoreKey, HighScoreTable, HighScores, HIGH_SCORES_MAX_PER_TABLE,
+};
+pub use keyboard::next_card_index;
+pub use learning::{LearningProgress, LetterProgress, BOX_MAX, QUICK_FLIPS};
+pub use lobby::{
+    Lobby, LobbyPlayer, PLAYER_NAME_MAX_CHARS, PRESENCE_INTERVAL_MS, PRESENCE_TIMEOUT_MS,
+};
+pub use query::query_value;
+pub use quiz::{Quiz, QuizAnswerKind, QuizPrompt, QuizQuestion, QUIZ_CHOICES, STREAK_POINTS_MAX};
+pub use settings::{GameSettings, MatchRule, DECKS, GRID_SIZES, TIME_LIMITS_S};
+pub use ws_message::{PresenceStatus, WireFormat, WsMessage};
+//endregion
+
+//region: enum, structs, const,...
+///the content folder has images and sounds from 01 to 26 for the alphabet
+pub const CARD_NUMBER_MAX: usize = 26;
+
+///the 3 possible states of one card
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CardStatusCardFace {
+    ///card face down
+    Down,
+    ///card face Up Temporary
+    UpTemporary,
+    ///card face up Permanently
+    UpPermanently,
+}
+
+///all the data for one card
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Card {
+    ///card status
+    pub status: CardStatusCardFace,
+    ///field for src attribute for HTML element imagea and filename of card image
+    pub card_number_and_img_src: usize,
+    ///field for id attribute for HTML element image contains the card index
+    pub card_index_and_id: usize,
+}
+
+///the reasons why the engine does not accept an action
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EngineError {
+    ///only the player whose turn it is can flip cards
+    NotYourTurn,
+    ///the card index is not in the grid
+    CardIndexOutOfRange,
+    ///only a card face down can be flipped
+    CardNotFaceDown,
+    ///two cards are already flipped in this turn
+    TooManyClicks,
+    ///the turn can be taken only after two cards that don't match
+    CannotTakeTurnNow,
+    ///the game is not in play
+    GameNotInPlay,
+    ///the click count in the message is not the next click
+    WrongClickCount,
+    ///the deck must have the placeholder and pairs of cards face down
+    InvalidDeck,
+    ///the settings are not in the allowed options
+    InvalidSettings,
+    ///the binary message cannot be decoded
+    InvalidEncoding,
+    ///the MAC of the sealed message is wrong
+    ForgedMessage,
+    ///the sequence number of the sealed message was already received
+    ReplayedMessage,
+    ///too many sealed messages wait for the `Ack`
+    TooManyUnacked,
+}
+
+///the server owns the engine: all the cards and the rules
+pub struct GameEngine {
+    ///vector of cards, index 0 is only a placeholder
+    pub vec_cards: Vec<Card>,
+    ///whose turn is now:  player 1 or 2
+    pub player_turn: usize,
+    ///count click inside one turn
+    pub count_click_inside_one_turn: usize,
+    ///card index of first click
+    pub card_index_of_first_click: usize,
+    ///card index of second click
+    pub card_index_of_second_click: usize,
+    ///player1 points
+    pub player1_points: usize,
+    ///player2 points
+    pub player2_points: usize,
+    ///who plays after a match
+    pub match_rule: MatchRule,
+}
+
+///what the players are allowed to see. The cards face down have the card number 0.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameView {
+    ///vector of cards, the face down cards are hidden
+    pub vec_cards: Vec<Card>,
+    ///whose turn is now:  player 1 or 2
+    pub player_turn: usize,
+    ///count click inside one turn
+    pub count_click_inside_one_turn: usize,
+    ///card index of first click
+    pub card_index_of_first_click: usize,
+    ///card index of second click
+    pub card_index_of_second_click: usize,
+    ///player1 points
+    pub player1_points: usize,
+    ///player2 points
+    pub player2_points: usize,
+}
+//endregion
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            Self::NotYourTurn => "it is not your turn",
+            Self::CardIndexOutOfRange => "the card index is out of range",
+            Self::CardNotFaceDown => "the card is not face down",
+            Self::TooManyClicks => "two cards are already flipped",
+            Self::CannotTakeTurnNow => "the turn cannot be taken now",
+            Self::GameNotInPlay => "the game is not in play",
+            Self::WrongClickCount => "the click count is not the next click",
+            Self::InvalidDeck => "the deck is not valid",
+            Self::InvalidSettings => "the settings are not valid",
+            Self::InvalidEncoding => "the binary message is not valid",
+            Self::ForgedMessage => "the message is not authentic",
+            Self::ReplayedMessage => "the message was already received",
+            Self::TooManyUnacked => "too many messages wait for the acknowledgement",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+///find distinct random numbers between 1 and 26 for the alphabet cards, make pairs and shuffle them.
+///
+///The settings allow at most 18 pairs.
+///Index 0 is special and reserved for `FaceDown`. Cards start with base 1.
+#[must_use]
+pub fn new_random_cards(card_pairs: usize) -> Vec<Card> {
+    //region: find distinct random numbers between 1 and 26 for the alphabet cards
+    //vec_of_random_numbers is 0 based
+    let mut vec_of_random_numbers = Vec::new();
+    let mut rng = SmallRng::from_entropy();
+    while vec_of_random_numbers.len() < card_pairs.min(CARD_NUMBER_MAX) {
+        //gen_range is lower inclusive, upper exclusive 26 + 1
+        let num: usize = rng.gen_range(1, CARD_NUMBER_MAX + 1);
+        if !vec_of_random_numbers.contains(&num) {
+            vec_of_random_numbers.push(num);
+        }
+    }
+    //endregion
+    new_cards_from_letters(&vec_of_random_numbers)
+}
+
+///make pairs of the chosen card numbers and shuffle them.
+///The learning mode chooses the letters, the other games choose them randomly.
+#[must_use]
+pub fn new_cards_from_letters(vec_card_numbers: &[usize]) -> Vec<Card> {
+    //push a pair of the same number
+    let mut vec_of_random_numbers: Vec<usize> = vec_card_numbers
+        .iter()
+        .flat_map(|card_number| vec![*card_number, *card_number])
+        .collect();
+    let mut rng = SmallRng::from_entropy();
+
+    //region: shuffle the numbers
+    vec_of_random_numbers.as_mut_slice().shuffle(&mut rng);
+    //endregion
+
+    //region: create Cards from random numbers
+    let mut vec_cards = Vec::new();
+    vec_cards.push(Card {
+        status: CardStatusCardFace::Down,
+        card_number_and_img_src: 0,
+        card_index_and_id: 0,
+    });
+    for (index, random_number) in vec_of_random_numbers.iter().enumerate() {
+        vec_cards.push(Card {
+            status: CardStatusCardFace::Down,
+            card_number_and_img_src: *random_number,
+            //card base index will be 1. 0 is reserved for FaceDown.
+            card_index_and_id: index + 1,
+        });
+    }
+    //endregion
+    vec_cards
+}
+
+///check if the player can flip the card. The client and the server use the same rules.
+///# Errors
+///Returns the reason if the flip is not allowed.
+pub fn check_flip(
+    vec_cards: &[Card],
+    player_turn: usize,
+    count_click_inside_one_turn: usize,
+    player: usize,
+    card_index: usize,
+) -> Result<(), EngineError> {
+    if player != player_turn {
+        return Err(EngineError::NotYourTurn);
+    }
+    if card_index == 0 || card_index >= vec_cards.len() {
+        return Err(EngineError::CardIndexOutOfRange);
+    }
+    if count_click_inside_one_turn >= 2 {
+        return Err(EngineError::TooManyClicks);
+    }
+    if vec_cards[card_index].status != CardStatusCardFace::Down {
+        return Err(EngineError::CardNotFaceDown);
+    }
+    Ok(())
+}
+
+///check if the player can take the turn: after two cards that don't match, the other player takes it.
+///# Errors
+///Returns the reason if it is not the time to take the turn.
+pub const fn check_take_turn(
+    player_turn: usize,
+    count_click_inside_one_turn: usize,
+    player: usize,
+) -> Result<(), EngineError> {
+    if count_click_inside_one_turn < 2 || player == player_turn {
+        return Err(EngineError::CannotTakeTurnNow);
+    }
+    Ok(())
+}
+
+///check the deck received from the other player: the placeholder, then pairs of valid cards face down
+///# Errors
+///Returns `InvalidDeck` if anything is wrong.
+pub fn check_deck(vec_cards: &[Card], card_pairs: usize) -> Result<(), EngineError> {
+    if vec_cards.len() != card_pairs * 2 + 1 {
+        return Err(EngineError::InvalidDeck);
+    }
+    for (index, card) in vec_cards.iter().enumerate().skip(1) {
+        let count_same = vec_cards
+            .iter()
+            .filter(|other| other.card_number_and_img_src == card.card_number_and_img_src)
+            .count();
+        if card.status != CardStatusCardFace::Down
+            || card.card_index_and_id != index
+            || card.card_number_and_img_src == 0
+            || card.card_number_and_img_src > CARD_NUMBER_MAX
+            || count_same != 2
+        {
+            return Err(EngineError::InvalidDeck);
+        }
+    }
+    Ok(())
+}
+
+impl GameEngine {
+    ///new game with shuffled cards. Player1 starts.
+    #[must_use]
+    pub const fn new(vec_cards: Vec<Card>, match_rule: MatchRule) -> Self {
+        Self {
+            vec_cards,
+            player_turn: 1,
+            count_click_inside_one_turn: 0,
+            card_index_of_first_click: 0,
+            card_index_of_second_click: 0,
+            player1_points: 0,
+            player2_points: 0,
+            match_rule,
+        }
+    }
+
+    ///the player flips a card. If it is the second card and they match, the player gets a point.
+    ///# Errors
+    ///Returns the reason if the flip is not allowed. Then nothing changes.
+    pub fn flip(&mut self, player: usize, card_index: usize) -> Result<(), EngineError> {
+        check_flip(
+            &self.vec_cards,
+            self.player_turn,
+            self.count_click_inside_one_turn,
+            player,
+            card_index,
+        )?;
+
+        self.count_click_inside_one_turn += 1;
+        if self.count_click_inside_one_turn == 1 {
+            self.card_index_of_first_click = card_index;
+            self.card_index_of_second_click = 0;
+        } else {
+            self.card_index_of_second_click = card_index;
+        }
+        self.vec_cards[card_index].status = CardStatusCardFace::UpTemporary;
+
+        if self.count_click_inside_one_turn == 2 {
+            let x1 = self.card_index_of_first_click;
+            let x2 = self.card_index_of_second_click;
+            if self.vec_cards[x1].card_number_and_img_src
+                == self.vec_cards[x2].card_number_and_img_src
+            {
+                //give points
+                if self.player_turn == 1 {
+                    self.player1_points += 1;
+                } else {
+                    self.player2_points += 1;
+                }
+                // the two cards matches. make them permanent FaceUp
+                self.vec_cards[x1].status = CardStatusCardFace::UpPermanently;
+                self.vec_cards[x2].status = CardStatusCardFace::UpPermanently;
+                self.count_click_inside_one_turn = 0;
+                //the match rule decides who plays next
+                if self.match_rule == MatchRule::PassTurn {
+                    self.player_turn = if self.player_turn == 1 { 2 } else { 1 };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    ///after two cards that don't match, the other player takes the turn and both cards are flipped back
+    ///# Errors
+    ///Returns the reason if it is not the time to take the turn. Then nothing changes.
+    pub fn take_turn(&mut self, player: usize) -> Result<(), EngineError> {
+        check_take_turn(self.player_turn, self.count_click_inside_one_turn, player)?;
+        self.player_turn = if self.player_turn == 1 { 2 } else { 1 };
+        let x1 = self.card_index_of_first_click;
+        let x2 = self.card_index_of_second_click;
+        self.vec_cards[x1].status = CardStatusCardFace::Down;
+        self.vec_cards[x2].status = CardStatusCardFace::Down;
+        self.card_index_of_first_click = 0;
+        self.card_index_of_second_click = 0;
+        self.count_click_inside_one_turn = 0;
+        Ok(())
+    }
+
+    ///the game is over when all the cards are face up permanently. Card 0 is only a placeholder.
+    #[must_use]
+    pub fn is_game_over(&self) -> bool {
+        self.vec_cards
+            .iter()
+            .skip(1)
+            .all(|card| card.status == CardStatusCardFace::UpPermanently)
+    }
+
+    ///the players see only the cards that are face up
+    #[must_use]
+    pub fn view(&self) -> GameView {
+        GameView {
+            vec_cards: self
+                .vec_cards
+                .iter()
+                .map(|card| Card {
+                    status: card.status,
+                    card_number_and_img_src: if card.status == CardStatusCardFace::Down {
+                        0
+                    } else {
+                        card.card_number_and_img_src
+                    },
+                    card_index_and_id: card.card_index_and_id,
+                })
+                .collect(),
+            player_turn: self.player_turn,
+            count_click_inside_one_turn: self.count_click_inside_one_turn,
+            card_index_of_first_click: self.card_index_of_first_click,
+            card_index_of_second_click: self.card_index_of_second_click,
+            player1_points: self.player1_points,
+            player2_points: self.player2_points,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///the cards in a known order: A B A B
+    fn engine() -> GameEngine {
+        let vec_cards = [0, 1, 2, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(index, card_number)| Card {
+                status: CardStatusCardFace::Down,
+                card_number_and_img_src: *card_number,
+                card_index_and_id: index,
+            })
+            .collect();
+        GameEngine::new(vec_cards, MatchRule::KeepTurn)
+    }
+
+    #[test]
+    fn matching_pair_gives_a_point() {
+        let mut engine = engine();
+        engine.flip(1, 1).unwrap();
+        engine.flip(1, 3).unwrap();
+        assert_eq!(engine.player1_points, 1);
+        assert_eq!(
+            engine.vec_cards[1].status,
+            CardStatusCardFace::UpPermanently
+        );
+        assert_eq!(
+            engine.vec_cards[3].status,
+            CardStatusCardFace::UpPermanently
+        );
+        //the same player continues
+        assert_eq!(engine.player_turn, 1);
+        assert_eq!(engine.count_click_inside_one_turn, 0);
+    }
+
+    #[test]
+    fn matching_pair_passes_the_turn_with_the_pass_rule() {
+        let mut engine = engine();
+        engine.match_rule = MatchRule::PassTurn;
+        engine.flip(1, 1).unwrap();
+        engine.flip(1, 3).unwrap();
+        assert_eq!(engine.player1_points, 1);
+        assert_eq!(engine.player_turn, 2);
+        assert_eq!(engine.flip(1, 2), Err(EngineError::NotYourTurn));
+    }
+
+    #[test]
+    fn flip_on_the_other_players_turn_is_rejected() {
+        let mut engine = engine();
+        assert_eq!(engine.flip(2, 1), Err(EngineError::NotYourTurn));
+        assert_eq!(engine.vec_cards[1].status, CardStatusCardFace::Down);
+    }
+
+    #[test]
+    fn flip_of_a_card_face_up_is_rejected() {
+        let mut engine = engine();
+        engine.flip(1, 1).unwrap();
+        assert_eq!(engine.flip(1, 1), Err(EngineError::CardNotFaceDown));
+        assert_eq!(engine.count_click_inside_one_turn, 1);
+    }
+
+    #[test]
+    fn flip_out_of_the_grid_is_rejected() {
+        let mut engine = engine();
+        assert_eq!(engine.flip(1, 0), Err(EngineError::CardIndexOutOfRange));
+        assert_eq!(engine.flip(1, 5), Err(EngineError::CardIndexOutOfRange));
+    }
+
+    #[test]
+    fn third_click_is_rejected() {
+        let mut engine = engine();
+        engine.flip(1, 1).unwrap();
+        engine.flip(1, 2).unwrap();
+        assert_eq!(engine.flip(1, 4), Err(EngineError::TooManyClicks));
+        assert_eq!(engine.vec_cards[4].status, CardStatusCardFace::Down);
+    }
+
+    #[test]
+    fn other_player_takes_the_turn_after_a_mismatch() {
+        let mut engine = engine();
+        engine.flip(1, 1).unwrap();
+        engine.flip(1, 2).unwrap();
+        assert_eq!(engine.vec_cards[2].status, CardStatusCardFace::UpTemporary);
+        //the player who missed cannot take the turn
+        assert_eq!(engine.take_turn(1), Err(EngineError::CannotTakeTurnNow));
+        engine.take_turn(2).unwrap();
+        assert_eq!(engine.player_turn, 2);
+        assert_eq!(engine.count_click_inside_one_turn, 0);
+        assert_eq!(engine.vec_cards[1].status, CardStatusCardFace::Down);
+        assert_eq!(engine.vec_cards[2].status, CardStatusCardFace::Down);
+        assert_eq!(engine.player1_points, 0);
+    }
+
+    #[test]
+    fn take_turn_before_two_cards_is_rejected() {
+        let mut engine = engine();
+        assert_eq!(engine.take_turn(2), Err(EngineError::CannotTakeTurnNow));
+        engine.flip(1, 1).unwrap();
+        assert_eq!(engine.take_turn(2), Err(EngineError::CannotTakeTurnNow));
+        assert_eq!(engine.player_turn, 1);
+    }
+
+    #[test]
+    fn game_is_over_when_all_pairs_are_found() {
+        let mut engine = engine();
+        engine.flip(1, 1).unwrap();
+        engine.flip(1, 3).unwrap();
+        assert!(!engine.is_game_over());
+        engine.flip(1, 2).unwrap();
+        engine.flip(1, 4).unwrap();
+        assert!(engine.is_game_over());
+        assert_eq!(engine.player1_points, 2);
+    }
+
+    #[test]
+    fn view_hides_the_cards_face_down() {
+        let mut engine = engine();
+        engine.flip(1, 2).unwrap();
+        let view = engine.view();
+        let vec_card_numbers: Vec<usize> = view
+            .vec_cards
+            .iter()
+            .map(|card| card.card_number_and_img_src)
+            .collect();
+        assert_eq!(vec_card_numbers, vec![0, 0, 2, 0, 0]);
+        assert_eq!(view.card_index_of_first_click, 2);
+    }
+
+    #[test]
+    fn check_deck_accepts_only_pairs_face_down() {
+        let engine = engine();
+        assert_eq!(check_deck(&engine.vec_cards, 2), Ok(()));
+        assert_eq!(
+            check_deck(&engine.vec_cards, 3),
+            Err(EngineError::InvalidDeck)
+        );
+        let mut vec_cards = engine.vec_cards.clone();
+        vec_cards[1].status = CardStatusCardFace::UpTemporary;
+        assert_eq!(check_deck(&vec_cards, 2), Err(EngineError::InvalidDeck));
+        let mut vec_cards = engine.vec_cards.clone();
+        vec_cards[1].card_number_and_img_src = 2;
+        assert_eq!(check_deck(&vec_cards, 2), Err(EngineError::InvalidDeck));
+        let mut vec_cards = engine.vec_cards;
+        vec_cards[4].card_index_and_id = 3;
+        assert_eq!(check_deck(&vec_cards, 2), Err(EngineError::InvalidDeck));
+    }
+
+    #[test]
+    fn random_cards_are_a_valid_deck() {
+        let vec_cards = new_random_cards(8);
+        assert_eq!(check_deck(&vec_cards, 8), Ok(()));
+    }
+}
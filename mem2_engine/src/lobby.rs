@@ -0,0 +1,121 @@
+//! `lobby.rs` - the list of players online, built from the `Presence` messages.
+//! Every client announces its name and status every few seconds.
+//! A player that was not heard for a while is removed from the list.
+//! The client renders the list on the start screen.
+
+use crate::ws_message::PresenceStatus;
+
+///the names from other players are cut to this length
+pub const PLAYER_NAME_MAX_CHARS: usize = 20;
+///every client announces itself every 5 seconds
+pub const PRESENCE_INTERVAL_MS: f64 = 5_000.0;
+///a player not heard for 15 seconds is gone
+pub const PRESENCE_TIMEOUT_MS: f64 = 15_000.0;
+
+///one player online
+#[derive(Debug)]
+pub struct LobbyPlayer {
+    ///ws client instance unique id
+    pub ws_client_instance: usize,
+    ///the name chosen by the player
+    pub player_name: String,
+    ///idle or in game
+    pub status: PresenceStatus,
+    ///time of the last `Presence` message from js `Date.now()`
+    pub last_seen: f64,
+}
+
+///all the players online, without me
+#[derive(Default, Debug)]
+pub struct Lobby {
+    ///in the order they were first seen
+    pub vec_players: Vec<LobbyPlayer>,
+}
+
+impl Lobby {
+    ///add the player or update the name and status
+    pub fn update(
+        &mut self,
+        ws_client_instance: usize,
+        player_name: &str,
+        status: PresenceStatus,
+        now: f64,
+    ) {
+        let player_name: String = player_name.chars().take(PLAYER_NAME_MAX_CHARS).collect();
+        match self
+            .vec_players
+            .iter_mut()
+            .find(|player| player.ws_client_instance == ws_client_instance)
+        {
+            Some(player) => {
+                player.player_name = player_name;
+                player.status = status;
+                player.last_seen = now;
+            }
+            None => self.vec_players.push(LobbyPlayer {
+                ws_client_instance,
+                player_name,
+                status,
+                last_seen: now,
+            }),
+        }
+    }
+
+    ///remove the players not heard for a while
+    pub fn prune(&mut self, now: f64) {
+        self.vec_players
+            .retain(|player| now - player.last_seen < PRESENCE_TIMEOUT_MS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_adds_a_new_player() {
+        let mut lobby = Lobby::default();
+        lobby.update(7, "Alice", PresenceStatus::Idle, 1000.0);
+        lobby.update(9, "Bob", PresenceStatus::Idle, 1000.0);
+        assert_eq!(lobby.vec_players.len(), 2);
+        assert_eq!(lobby.vec_players[1].player_name, "Bob");
+    }
+
+    #[test]
+    fn update_changes_the_existing_player() {
+        let mut lobby = Lobby::default();
+        lobby.update(7, "Alice", PresenceStatus::Idle, 1000.0);
+        lobby.update(7, "Alicia", PresenceStatus::InGame, 6000.0);
+        assert_eq!(lobby.vec_players.len(), 1);
+        let player = &lobby.vec_players[0];
+        assert_eq!(player.player_name, "Alicia");
+        assert_eq!(player.status, PresenceStatus::InGame);
+        assert!((player.last_seen - 6000.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn long_names_are_cut() {
+        let mut lobby = Lobby::default();
+        lobby.update(7, &"x".repeat(50), PresenceStatus::Idle, 1000.0);
+        assert_eq!(
+            lobby.vec_players[0].player_name.chars().count(),
+            PLAYER_NAME_MAX_CHARS
+        );
+    }
+
+    #[test]
+    fn prune_removes_only_the_stale_players() {
+        let mut lobby = Lobby::default();
+        lobby.update(7, "Alice", PresenceStatus::Idle, 1000.0);
+        lobby.update(9, "Bob", PresenceStatus::Idle, 5000.0);
+        lobby.prune(1000.0 + PRESENCE_TIMEOUT_MS - 1.0);
+        assert_eq!(lobby.vec_players.len(), 2);
+        lobby.prune(1000.0 + PRESENCE_TIMEOUT_MS);
+        assert_eq!(lobby.vec_players.len(), 1);
+        assert_eq!(lobby.vec_players[0].ws_client_instance, 9);
+        //a new Presence keeps the player in the lobby
+        lobby.update(9, "Bob", PresenceStatus::Idle, 19_000.0);
+        lobby.prune(5000.0 + PRESENCE_TIMEOUT_MS);
+        assert_eq!(lobby.vec_players.len(), 1);
+    }
+}
@@ -0,0 +1,218 @@
+//! `morse.rs` - the Morse code of any text.
+//!
+//! The client plays the tones with Web Audio and shows the dots and dashes on the screen.
+//! Both use this one table and this one timing, so the picture and the sound are always the same.
+//! The timing is the standard PARIS timing: a dot is 1 unit, a dash 3 units, the gap inside a letter 1 unit,
+//! the gap between letters 3 units and the gap between words 7 units. One unit is 1200 ms divided by the words per minute.
+
+///the letters, digits and some punctuation. Lowercase letters use the uppercase code.
+const MORSE_TABLE: &[(char, &str)] = &[
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('!', "-.-.--"),
+    ('-', "-....-"),
+    ('/', "-..-."),
+    ('\'', ".----."),
+];
+
+///one dot or dash in the timeline of a text
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tone {
+    ///'.' or '-'
+    pub symbol: char,
+    ///the position of the letter in the text
+    pub letter_index: usize,
+    ///the tone starts this many milliseconds after the start of the text
+    pub start_ms: f64,
+    ///how long the tone sounds
+    pub duration_ms: f64,
+}
+
+///the Morse code of one character. None if the table does not have it.
+#[must_use]
+pub fn letter_code(letter: char) -> Option<&'static str> {
+    let letter = letter.to_ascii_uppercase();
+    MORSE_TABLE
+        .iter()
+        .find(|(table_letter, _)| *table_letter == letter)
+        .map(|(_, code)| *code)
+}
+
+///the label of the card number: 1 is A, 26 is Z. The card face down 0 has no label.
+#[must_use]
+pub fn card_letter(card_number: usize) -> Option<char> {
+    //the table starts with the alphabet
+    if card_number == 0 || card_number > crate::CARD_NUMBER_MAX {
+        None
+    } else {
+        MORSE_TABLE.get(card_number - 1).map(|(letter, _)| *letter)
+    }
+}
+
+///the Morse code of the text: a space between letters and a slash between words.
+///The characters without code are skipped.
+#[must_use]
+pub fn encode(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter_map(letter_code)
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<String>>()
+        .join(" / ")
+}
+
+///one unit of the PARIS timing in milliseconds
+#[must_use]
+pub fn unit_ms(wpm: u32) -> f64 {
+    1200.0 / f64::from(wpm.max(1))
+}
+
+///when every dot and dash of the text sounds. The characters without code are silent and take no time.
+#[must_use]
+pub fn tones(text: &str, wpm: u32) -> Vec<Tone> {
+    let unit = unit_ms(wpm);
+    let mut vec_tones = Vec::new();
+    let mut time_ms = 0.0;
+    //the gap before the next tone: none at the start, then the letter or the word gap
+    let mut gap_units = 0.0;
+    for (letter_index, letter) in text.chars().enumerate() {
+        if letter.is_whitespace() {
+            if !vec_tones.is_empty() {
+                gap_units = 7.0;
+            }
+            continue;
+        }
+        if let Some(code) = letter_code(letter) {
+            for (symbol_index, symbol) in code.chars().enumerate() {
+                if symbol_index > 0 {
+                    gap_units = 1.0;
+                }
+                time_ms += gap_units * unit;
+                let duration_ms = if symbol == '-' { 3.0 * unit } else { unit };
+                vec_tones.push(Tone {
+                    symbol,
+                    letter_index,
+                    start_ms: time_ms,
+                    duration_ms,
+                });
+                time_ms += duration_ms;
+            }
+            //the word gap is kept if it was already set by a whitespace
+            gap_units = 3.0;
+        }
+    }
+    vec_tones
+}
+
+///how long the whole text sounds
+#[must_use]
+pub fn duration_ms(vec_tones: &[Tone]) -> f64 {
+    vec_tones
+        .last()
+        .map_or(0.0, |tone| tone.start_ms + tone.duration_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_and_words_are_encoded() {
+        assert_eq!(encode("sos"), "... --- ...");
+        assert_eq!(encode("Hi 5"), ".... .. / .....");
+        assert_eq!(encode("a#b"), ".- -...");
+        assert_eq!(encode("   "), "");
+    }
+
+    #[test]
+    fn every_alphabet_card_has_a_code() {
+        for letter in 'A'..='Z' {
+            assert!(letter_code(letter).is_some(), "{}", letter);
+        }
+    }
+
+    #[test]
+    fn card_numbers_are_letters() {
+        assert_eq!(card_letter(0), None);
+        assert_eq!(card_letter(1), Some('A'));
+        assert_eq!(card_letter(26), Some('Z'));
+        assert_eq!(card_letter(27), None);
+    }
+
+    #[test]
+    fn paris_timing() {
+        //at 12 wpm one unit is 100 ms
+        let vec_tones = tones("ae", 12);
+        let vec_start: Vec<f64> = vec_tones.iter().map(|tone| tone.start_ms).collect();
+        let vec_duration: Vec<f64> = vec_tones.iter().map(|tone| tone.duration_ms).collect();
+        //A is dot gap dash, then the letter gap, then E is a dot
+        assert_eq!(vec_start, vec![0.0, 200.0, 800.0]);
+        assert_eq!(vec_duration, vec![100.0, 300.0, 100.0]);
+        assert!((duration_ms(&vec_tones) - 900.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn word_gap_is_seven_units() {
+        let vec_tones = tones("e e", 12);
+        //the dot of the first E ends at 100 ms
+        let gap_ms = vec_tones[1].start_ms - 100.0;
+        assert!((gap_ms - 700.0).abs() < f64::EPSILON);
+        assert_eq!(vec_tones[1].letter_index, 2);
+    }
+
+    #[test]
+    fn unknown_characters_take_no_time() {
+        assert_eq!(
+            tones("e#e", 12),
+            tones("ee", 12)
+                .iter()
+                .map(|tone| Tone {
+                    letter_index: tone.letter_index * 2,
+                    ..*tone
+                })
+                .collect::<Vec<Tone>>()
+        );
+        assert!(tones("###", 12).is_empty());
+    }
+}
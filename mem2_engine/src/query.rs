@@ -0,0 +1,48 @@
+//! `query.rs` - the options of the page in the url query, like `?transport=broadcast&encoding=json`.
+//!
+//! Only whole pairs count: `?transport=broadcaster` is not `broadcast` and `?xtransport=broadcast` is not `transport`.
+//! The first pair with the name wins.
+
+///the value of the option in the url query. None if the query has no pair with this name.
+#[must_use]
+pub fn query_value<'a>(search: &'a str, name: &str) -> Option<&'a str> {
+    search.trim_start_matches('?').split('&').find_map(|pair| {
+        let mut name_and_value = pair.splitn(2, '=');
+        if name_and_value.next() == Some(name) {
+            Some(name_and_value.next().unwrap_or(""))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_of_the_pair_with_the_name() {
+        let search = "?transport=broadcast&encoding=json";
+        assert_eq!(query_value(search, "transport"), Some("broadcast"));
+        assert_eq!(query_value(search, "encoding"), Some("json"));
+        assert_eq!(query_value(search, "disconnect_timeout"), None);
+    }
+
+    #[test]
+    fn only_whole_names_match() {
+        assert_eq!(query_value("?xtransport=broadcast", "transport"), None);
+        assert_eq!(query_value("?transport_x=broadcast", "transport"), None);
+        //the value is whole too, the caller compares it exactly
+        assert_eq!(
+            query_value("?transport=broadcaster", "transport"),
+            Some("broadcaster")
+        );
+    }
+
+    #[test]
+    fn first_pair_wins_and_empty_values_are_empty() {
+        assert_eq!(query_value("?a=1&a=2", "a"), Some("1"));
+        assert_eq!(query_value("?a&b=2", "a"), Some(""));
+        assert_eq!(query_value("", "a"), None);
+    }
+}
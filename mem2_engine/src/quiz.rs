@@ -0,0 +1,207 @@
+//! `quiz.rs` - the quiz mini-game beside the memory grid.
+//!
+//! The quiz shows the image of a card or plays its Morse code. The child picks the right letter
+//! or spelling word from 4 choices. Every right answer in a row makes the streak longer
+//! and the streak is the points of the answer, up to 5. A wrong answer ends the streak.
+//! The letters come from the learning progress, so the hard letters are asked more often.
+
+use crate::LearningProgress;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+///the choices of one question
+pub const QUIZ_CHOICES: usize = 4;
+///a long streak gives at most so many points for one answer
+pub const STREAK_POINTS_MAX: u32 = 5;
+
+///what the question shows
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QuizPrompt {
+    ///the image of the card
+    Image,
+    ///the Morse code of the letter, only the sound
+    Morse,
+}
+
+///what the choices are
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QuizAnswerKind {
+    ///the letter like A
+    Letter,
+    ///the spelling word like alpha
+    Word,
+}
+
+///one question and the answer of the child
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct QuizQuestion {
+    ///the right answer
+    pub card_number: usize,
+    ///image or Morse
+    pub prompt: QuizPrompt,
+    ///letters or words
+    pub answer_kind: QuizAnswerKind,
+    ///card numbers of the choices in the order on the screen. One of them is the right answer.
+    pub vec_choices: Vec<usize>,
+    ///the card number the child picked
+    pub chosen: Option<usize>,
+}
+
+impl QuizQuestion {
+    ///None until the child picks a choice
+    #[must_use]
+    pub fn is_correct(&self) -> Option<bool> {
+        self.chosen.map(|chosen| chosen == self.card_number)
+    }
+}
+
+///the score of the quiz and the current question
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Quiz {
+    ///points of all the right answers
+    pub score: u32,
+    ///right answers in a row
+    pub streak: u32,
+    ///the longest streak in this quiz
+    pub best_streak: u32,
+    ///answered questions
+    pub count_answered: u32,
+    ///right answers
+    pub count_correct: u32,
+    ///the current question
+    pub question: Option<QuizQuestion>,
+}
+
+impl Quiz {
+    ///the next question with letters from the learning progress.
+    ///The right answer is not the same as in the last question.
+    pub fn new_question<R: Rng>(&mut self, learning: &LearningProgress, rng: &mut R) {
+        let mut vec_choices = learning.choose_letters(QUIZ_CHOICES, rng);
+        let last_card_number = self.question.as_ref().map(|question| question.card_number);
+        //the first chosen letter has the best chance of the learning progress
+        let card_number = vec_choices
+            .iter()
+            .copied()
+            .find(|card_number| Some(*card_number) != last_card_number)
+            .unwrap_or(0);
+        vec_choices.shuffle(rng);
+        self.question = Some(QuizQuestion {
+            card_number,
+            prompt: if rng.gen() {
+                QuizPrompt::Image
+            } else {
+                QuizPrompt::Morse
+            },
+            answer_kind: if rng.gen() {
+                QuizAnswerKind::Letter
+            } else {
+                QuizAnswerKind::Word
+            },
+            vec_choices,
+            chosen: None,
+        });
+    }
+
+    ///the child picks a choice. Returns if it is right.
+    ///None if there is no question, the question is already answered or it is not one of the choices.
+    pub fn answer(&mut self, card_number: usize) -> Option<bool> {
+        let question = self.question.as_mut()?;
+        if question.chosen.is_some() || !question.vec_choices.contains(&card_number) {
+            return None;
+        }
+        question.chosen = Some(card_number);
+        let is_correct = card_number == question.card_number;
+        self.count_answered += 1;
+        if is_correct {
+            self.count_correct += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+            self.score += self.streak.min(STREAK_POINTS_MAX);
+        } else {
+            self.streak = 0;
+        }
+        Some(is_correct)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    fn quiz_with_question(card_number: usize) -> Quiz {
+        Quiz {
+            question: Some(QuizQuestion {
+                card_number,
+                prompt: QuizPrompt::Image,
+                answer_kind: QuizAnswerKind::Letter,
+                vec_choices: vec![1, 2, 3, 4],
+                chosen: None,
+            }),
+            ..Quiz::default()
+        }
+    }
+
+    #[test]
+    fn question_has_the_answer_among_distinct_choices() {
+        let learning = LearningProgress::default();
+        let mut rng = SmallRng::seed_from_u64(3);
+        let mut quiz = Quiz::default();
+        for _ in 0..50 {
+            let last = quiz.question.as_ref().map(|question| question.card_number);
+            quiz.new_question(&learning, &mut rng);
+            let question = quiz.question.clone().unwrap();
+            assert_eq!(question.vec_choices.len(), QUIZ_CHOICES);
+            assert!(question.vec_choices.contains(&question.card_number));
+            assert_ne!(Some(question.card_number), last);
+            let mut vec_sorted = question.vec_choices.clone();
+            vec_sorted.sort_unstable();
+            vec_sorted.dedup();
+            assert_eq!(vec_sorted.len(), QUIZ_CHOICES);
+        }
+    }
+
+    #[test]
+    fn streak_gives_more_points() {
+        let mut quiz = quiz_with_question(2);
+        assert_eq!(quiz.answer(2), Some(true));
+        quiz.question = quiz_with_question(3).question;
+        assert_eq!(quiz.answer(3), Some(true));
+        assert_eq!(quiz.score, 1 + 2);
+        assert_eq!(quiz.streak, 2);
+    }
+
+    #[test]
+    fn wrong_answer_ends_the_streak() {
+        let mut quiz = quiz_with_question(2);
+        quiz.answer(2);
+        quiz.question = quiz_with_question(3).question;
+        assert_eq!(quiz.answer(4), Some(false));
+        assert_eq!(quiz.streak, 0);
+        assert_eq!(quiz.best_streak, 1);
+        assert_eq!(quiz.count_answered, 2);
+        assert_eq!(quiz.count_correct, 1);
+        assert_eq!(quiz.question.unwrap().is_correct(), Some(false));
+    }
+
+    #[test]
+    fn points_of_one_answer_have_a_maximum() {
+        let mut quiz = Quiz {
+            streak: 10,
+            ..quiz_with_question(1)
+        };
+        quiz.answer(1);
+        assert_eq!(quiz.score, STREAK_POINTS_MAX);
+    }
+
+    #[test]
+    fn only_one_answer_per_question() {
+        let mut quiz = quiz_with_question(1);
+        assert_eq!(quiz.answer(9), None);
+        assert_eq!(quiz.answer(2), Some(false));
+        assert_eq!(quiz.answer(1), None);
+        assert_eq!(quiz.count_answered, 1);
+        assert_eq!(Quiz::default().answer(1), None);
+    }
+}
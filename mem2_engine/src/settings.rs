@@ -0,0 +1,144 @@
+//! `settings.rs` - the game settings the two players agree on in the invitation handshake.
+//! The player that asks proposes the settings. The other player accepts them or counter-proposes.
+//! Only the options in these lists are allowed, so a client cannot propose something the other cannot play.
+
+use crate::EngineError;
+
+///the allowed grid sizes: columns and rows. The number of cards must be even.
+pub const GRID_SIZES: &[(usize, usize)] = &[(4, 3), (4, 4), (6, 4), (6, 6)];
+///the allowed decks are the content folders with images and sounds
+pub const DECKS: &[&str] = &["content", "content02"];
+///the allowed time limits for the whole game in seconds. 0 is without a limit.
+pub const TIME_LIMITS_S: &[u32] = &[0, 60, 120, 300];
+
+///what happens after a player finds a pair
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchRule {
+    ///the player gets a point and plays again
+    KeepTurn,
+    ///the player gets a point and the other player plays
+    PassTurn,
+}
+
+///everything the two players must agree on before the game starts
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct GameSettings {
+    ///number of columns in the grid
+    pub grid_columns: usize,
+    ///number of rows in the grid
+    pub grid_rows: usize,
+    ///the content folder name
+    pub deck: String,
+    ///what happens after a match
+    pub match_rule: MatchRule,
+    ///the game is over after this many seconds. 0 is without a limit.
+    pub time_limit_s: u32,
+    ///after two cards that don't match, they are flipped back automatically
+    pub auto_flip: bool,
+}
+
+impl Default for GameSettings {
+    ///the settings of the original game
+    fn default() -> Self {
+        Self {
+            grid_columns: 4,
+            grid_rows: 4,
+            deck: "content".to_string(),
+            match_rule: MatchRule::KeepTurn,
+            time_limit_s: 0,
+            auto_flip: false,
+        }
+    }
+}
+
+impl GameSettings {
+    ///how many different pairs of cards are in the grid
+    #[must_use]
+    pub const fn card_pairs(&self) -> usize {
+        self.grid_columns * self.grid_rows / 2
+    }
+
+    ///check the settings received from the other player
+    ///# Errors
+    ///Returns `InvalidSettings` if an option is not in the allowed lists.
+    pub fn check(&self) -> Result<(), EngineError> {
+        if GRID_SIZES.contains(&(self.grid_columns, self.grid_rows))
+            && DECKS.contains(&self.deck.as_str())
+            && TIME_LIMITS_S.contains(&self.time_limit_s)
+        {
+            Ok(())
+        } else {
+            Err(EngineError::InvalidSettings)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(
+        grid_columns: usize,
+        grid_rows: usize,
+        deck: &str,
+        time_limit_s: u32,
+    ) -> GameSettings {
+        GameSettings {
+            grid_columns,
+            grid_rows,
+            deck: deck.to_string(),
+            time_limit_s,
+            ..GameSettings::default()
+        }
+    }
+
+    #[test]
+    fn every_allowed_combination_is_accepted() {
+        for (grid_columns, grid_rows) in GRID_SIZES.iter().copied() {
+            for deck in DECKS {
+                for time_limit_s in TIME_LIMITS_S.iter().copied() {
+                    for match_rule in &[MatchRule::KeepTurn, MatchRule::PassTurn] {
+                        for auto_flip in &[false, true] {
+                            let mut game_settings =
+                                settings(grid_columns, grid_rows, deck, time_limit_s);
+                            game_settings.match_rule = *match_rule;
+                            game_settings.auto_flip = *auto_flip;
+                            assert_eq!(game_settings.check(), Ok(()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn odd_or_unknown_grid_is_rejected() {
+        //3x3 has an odd number of cards, 8x8 is even but not in the list
+        for (grid_columns, grid_rows) in &[(3, 3), (5, 3), (8, 8), (0, 0)] {
+            assert_eq!(
+                settings(*grid_columns, *grid_rows, "content", 0).check(),
+                Err(EngineError::InvalidSettings)
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_deck_is_rejected() {
+        for deck in &["content03", "", "../content"] {
+            assert_eq!(
+                settings(4, 4, deck, 0).check(),
+                Err(EngineError::InvalidSettings)
+            );
+        }
+    }
+
+    #[test]
+    fn disallowed_time_limit_is_rejected() {
+        for time_limit_s in &[1, 59, 90, 600] {
+            assert_eq!(
+                settings(4, 4, "content", *time_limit_s).check(),
+                Err(EngineError::InvalidSettings)
+            );
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! `ws_message.rs` - the messages sent over WebSocket between the clients and the server.
+//! `serde_json` can find out the variant of `WsMessage` from the json.
+//! The same messages can be sent as compact binary frames with `bincode`, if both clients agree in the handshake.
+//! JSON stays the fallback, because it is easy to read in the browser developer tools.
+
+use crate::{Card, EngineError, GameSettings, GameView};
+
+///how the messages are encoded on the wire
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WireFormat {
+    ///text frames with json, easy to debug
+    Json,
+    ///compact binary frames with bincode
+    Binary,
+}
+
+impl WireFormat {
+    ///binary only if both clients want binary, else the json fallback
+    #[must_use]
+    pub const fn agree(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Binary, Self::Binary) => Self::Binary,
+            _ => Self::Json,
+        }
+    }
+}
+
+///the status of a client in the lobby
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PresenceStatus {
+    ///on the start screen, can be challenged
+    Idle,
+    ///invited or playing
+    InGame,
+}
+
+///`WsMessage` enum for websocket
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum WsMessage {
+    ///connection test
+    ConnectionTest {
+        ///anything
+        test: String,
+    },
+    ///want to play: the challenge is only for the player chosen in the lobby
+    WantToPlay {
+        ///ws client instance unique id. To not listen the echo to yourself.
+        ws_client_instance: usize,
+        ///ws client instance unique id of the challenged player
+        other_ws_client_instance: usize,
+        ///the proposed settings
+        settings: GameSettings,
+        ///the wire format the sender wants for the game
+        wire_format: WireFormat,
+        ///random number for the session key of this game
+        nonce: u64,
+    },
+    ///the asked player answers the invitation with other settings
+    CounterPropose {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///ws client instance unique id of the receiver
+        other_ws_client_instance: usize,
+        ///the proposed settings
+        settings: GameSettings,
+        ///the wire format the sender wants for the game
+        wire_format: WireFormat,
+        ///random number for the session key of this game
+        nonce: u64,
+    },
+    ///every client announces itself to the lobby every few seconds and on every change
+    Presence {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///the name chosen by the player
+        player_name: String,
+        ///only idle players can be challenged
+        status: PresenceStatus,
+    },
+    /// accept play
+    AcceptPlay {
+        ///ws client instance unique id. To not listen the echo to yourself.
+        ws_client_instance: usize,
+        ///the shuffled cards, both players need the cards in the same location
+        vec_cards: Vec<Card>,
+        ///the accepted settings. They must be the proposed settings.
+        settings: GameSettings,
+        ///the agreed wire format for the rest of the game
+        wire_format: WireFormat,
+        ///random number for the session key of this game
+        nonce: u64,
+    },
+    ///player click
+    PlayerClick {
+        ///ws client instance unique id. To not listen the echo to yourself.
+        ws_client_instance: usize,
+        ///card index
+        card_index: usize,
+        ///count click inside one turn
+        count_click_inside_one_turn: usize,
+    },
+    ///player change
+    PlayerChange {
+        ///ws client instance unique id. To not listen the echo to yourself.
+        ws_client_instance: usize,
+    },
+    ///the player that asked does not wait anymore
+    CancelInvite {
+        ///ws client instance unique id of the player that asked
+        ws_client_instance: usize,
+    },
+    ///the asked player does not want to play
+    DeclinePlay {
+        ///ws client instance unique id of the asked player
+        ws_client_instance: usize,
+        ///ws client instance unique id of the player that asked
+        other_ws_client_instance: usize,
+    },
+    ///heartbeat: are you still there? It is sent every few seconds in the game.
+    Ping {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///ws client instance unique id of the receiver
+        other_ws_client_instance: usize,
+        ///time of sending from js `Date.now()`. The `Pong` returns it.
+        sent_at: f64,
+    },
+    ///heartbeat: the answer to `Ping`. The sender measures the round-trip latency.
+    Pong {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///ws client instance unique id of the receiver
+        other_ws_client_instance: usize,
+        ///the time from the `Ping`
+        sent_at: f64,
+    },
+    ///a game message authenticated with the session key
+    Sealed {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///ws client instance unique id of the receiver
+        other_ws_client_instance: usize,
+        ///every sealed message of the sender has the next number. Old numbers are replays.
+        sequence: u64,
+        ///the game message inside
+        msg: Box<Self>,
+        ///HMAC-SHA256 of sender, receiver, sequence and message
+        mac: Vec<u8>,
+    },
+    ///the receiver has applied all the sealed messages until this sequence number
+    Ack {
+        ///ws client instance unique id of the sender of the `Ack`
+        ws_client_instance: usize,
+        ///ws client instance unique id of the sender of the sealed messages
+        other_ws_client_instance: usize,
+        ///the last sequence number applied in order
+        sequence: u64,
+        ///HMAC-SHA256 of sender, receiver and sequence
+        mac: Vec<u8>,
+    },
+    ///a `Ping` or `Pong` authenticated with the session key. It is not acknowledged and not sent again.
+    SealedHeartbeat {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///ws client instance unique id of the receiver
+        other_ws_client_instance: usize,
+        ///the heartbeats have their own numbers. Old numbers are dropped.
+        sequence: u64,
+        ///the `Ping` or `Pong` inside
+        msg: Box<Self>,
+        ///HMAC-SHA256 of sender, receiver, sequence and message
+        mac: Vec<u8>,
+    },
+    ///a lobby or invitation message authenticated with the room key of the invite link
+    RoomSealed {
+        ///the lobby or invitation message inside
+        msg: Box<Self>,
+        ///HMAC-SHA256 of the message
+        mac: Vec<u8>,
+    },
+    ///the received message was not valid and was rejected
+    ProtocolError {
+        ///ws client instance unique id of the sender of the error
+        ws_client_instance: usize,
+        ///ws client instance unique id of the sender of the rejected message
+        other_ws_client_instance: usize,
+        ///why the message was rejected
+        reason: String,
+    },
+    //region: server-authoritative mode
+    ///client to server: I want to play a game with the server rules
+    AuthJoin {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///the settings of the game, if this client waits for the other player
+        settings: GameSettings,
+    },
+    ///client to server: I want to flip this card. Only the server knows what is on it.
+    AuthFlip {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+        ///card index
+        card_index: usize,
+    },
+    ///client to server: I take my turn after the other player's cards did not match
+    AuthTakeTurn {
+        ///ws client instance unique id of the sender
+        ws_client_instance: usize,
+    },
+    ///server to client: the game starts
+    AuthStart {
+        ///ws client instance unique id of the receiver
+        ws_client_instance: usize,
+        ///ws client instance unique id of the other player
+        other_ws_client_instance: usize,
+        ///player 1 or 2
+        player_number: usize,
+        ///the settings of the player that waited
+        settings: GameSettings,
+        ///the cards face down
+        game_view: GameView,
+    },
+    ///server to clients: the new state after an accepted action
+    AuthView {
+        ///ws client instance unique id of both players
+        vec_ws_client_instance: Vec<usize>,
+        ///only the face up cards are visible
+        game_view: GameView,
+    },
+    ///server to client: the action was not accepted, or the game ended before all the cards matched
+    AuthError {
+        ///ws client instance unique id of the receiver
+        ws_client_instance: usize,
+        ///why the action was not accepted
+        reason: String,
+        ///the server ended the game, for example the other player left
+        is_game_over: bool,
+    },
+    //endregion
+}
+
+impl WsMessage {
+    ///encode the message in a compact binary frame
+    ///# Errors
+    ///Returns `InvalidEncoding` if bincode cannot serialize the message.
+    pub fn to_binary(&self) -> Result<Vec<u8>, EngineError> {
+        bincode::serialize(self).map_err(|_| EngineError::InvalidEncoding)
+    }
+
+    ///decode the message from a binary frame
+    ///# Errors
+    ///Returns `InvalidEncoding` if the bytes are not a valid `WsMessage`.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, EngineError> {
+        bincode::deserialize(bytes).map_err(|_| EngineError::InvalidEncoding)
+    }
+
+    ///the lobby and the invitation messages are accepted only sealed with the room key.
+    ///Else anybody on the relay could invite, accept or cancel for another player.
+    #[must_use]
+    pub const fn needs_room_seal(&self) -> bool {
+        matches!(
+            self,
+            Self::Presence { .. }
+                | Self::WantToPlay { .. }
+                | Self::CounterPropose { .. }
+                | Self::AcceptPlay { .. }
+                | Self::CancelInvite { .. }
+                | Self::DeclinePlay { .. }
+        )
+    }
+
+    ///only the server sends these messages. The relay drops them from the clients,
+    ///so nobody else can push a board in the server-authoritative mode.
+    #[must_use]
+    pub const fn is_server_only(&self) -> bool {
+        matches!(
+            self,
+            Self::AuthStart { .. } | Self::AuthView { .. } | Self::AuthError { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///a message with a nested deck
+    fn accept_play() -> WsMessage {
+        WsMessage::AcceptPlay {
+            ws_client_instance: 1234,
+            vec_cards: crate::new_random_cards(GameSettings::default().card_pairs()),
+            settings: GameSettings::default(),
+            wire_format: WireFormat::Binary,
+            nonce: 42,
+        }
+    }
+
+    #[test]
+    fn only_the_server_messages_are_server_only() {
+        assert!(WsMessage::AuthError {
+            ws_client_instance: 1234,
+            reason: String::new(),
+            is_game_over: false,
+        }
+        .is_server_only());
+        assert!(!WsMessage::AuthJoin {
+            ws_client_instance: 1234,
+            settings: GameSettings::default(),
+        }
+        .is_server_only());
+        assert!(!accept_play().is_server_only());
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let bytes = accept_play().to_binary().unwrap();
+        match WsMessage::from_binary(&bytes).unwrap() {
+            WsMessage::AcceptPlay {
+                ws_client_instance,
+                vec_cards,
+                settings,
+                wire_format,
+                nonce,
+            } => {
+                assert_eq!(ws_client_instance, 1234);
+                assert!(crate::check_deck(&vec_cards, settings.card_pairs()).is_ok());
+                assert_eq!(settings, GameSettings::default());
+                assert_eq!(wire_format, WireFormat::Binary);
+                assert_eq!(nonce, 42);
+            }
+            _ => panic!("wrong variant"),
+        }
+    }
+
+    #[test]
+    fn binary_is_smaller_than_json() {
+        let msg = accept_play();
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(msg.to_binary().unwrap().len() < json.len());
+    }
+
+    #[test]
+    fn invalid_bytes_are_rejected() {
+        assert_eq!(
+            WsMessage::from_binary(&[255, 255, 255, 255]).err(),
+            Some(EngineError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn binary_only_if_both_agree() {
+        assert_eq!(
+            WireFormat::Binary.agree(WireFormat::Binary),
+            WireFormat::Binary
+        );
+        assert_eq!(WireFormat::Binary.agree(WireFormat::Json), WireFormat::Json);
+        assert_eq!(WireFormat::Json.agree(WireFormat::Binary), WireFormat::Json);
+        assert_eq!(WireFormat::Json.agree(WireFormat::Json), WireFormat::Json);
+    }
+}
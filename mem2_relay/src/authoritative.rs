@@ -0,0 +1,598 @@
+//! authoritative.rs - the server-authoritative game mode.
+//! The server owns the deck and the rules in a `GameEngine` from mem2_engine.
+//! The clients send only the intents `AuthFlip` and `AuthTakeTurn`.
+//! The server validates them and sends the new `GameView` to both players.
+//! The cards face down are never sent, so a client cannot learn a card before it is flipped.
+//! The game uses the checked settings of the player that waited, like the invitation uses the settings of the player that asks.
+//! Only the server sends `AuthStart`, `AuthView` and `AuthError`. From a client they are dropped, not relayed.
+
+use crate::{send_to, ServerState};
+use mem2_engine::{new_random_cards, GameEngine, GameSettings, WsMessage};
+
+///one player in the server-authoritative mode
+#[derive(Clone, Copy)]
+pub struct AuthPlayer {
+    ///id of the WebSocket connection on the server
+    pub conn_id: usize,
+    ///ws client instance unique id of the client
+    pub ws_client_instance: usize,
+}
+
+///the player that waits for the second player, with the settings of the game
+pub struct AuthWaiting {
+    ///the first player
+    pub player: AuthPlayer,
+    ///the settings are checked before the player waits
+    pub settings: GameSettings,
+}
+
+///a game owned by the server
+pub struct AuthGame {
+    ///cards and rules
+    engine: GameEngine,
+    ///player1 and player2
+    players: [AuthPlayer; 2],
+}
+
+///returns true if the message is for the server-authoritative mode and was handled here.
+///All other messages are relayed to the other clients.
+pub fn handle_auth_message(state: &mut ServerState, conn_id: usize, msg: &WsMessage) -> bool {
+    if msg.is_server_only() {
+        println!("dropped a server message from client {}", conn_id);
+        return true;
+    }
+    match *msg {
+        WsMessage::AuthJoin {
+            ws_client_instance,
+            ref settings,
+        } => {
+            join(
+                state,
+                AuthPlayer {
+                    conn_id,
+                    ws_client_instance,
+                },
+                settings,
+            );
+            true
+        }
+        WsMessage::AuthFlip {
+            ws_client_instance,
+            card_index,
+        } => {
+            play(
+                state,
+                AuthPlayer {
+                    conn_id,
+                    ws_client_instance,
+                },
+                |engine, player| engine.flip(player, card_index),
+            );
+            true
+        }
+        WsMessage::AuthTakeTurn { ws_client_instance } => {
+            play(
+                state,
+                AuthPlayer {
+                    conn_id,
+                    ws_client_instance,
+                },
+                |engine, player| engine.take_turn(player),
+            );
+            true
+        }
+        //the server does not know the room key, but the waiting player is found by the connection
+        WsMessage::CancelInvite { .. } => {
+            cancel_waiting(state, conn_id);
+            false
+        }
+        WsMessage::RoomSealed { ref msg, .. } => {
+            if let WsMessage::CancelInvite { .. } = **msg {
+                cancel_waiting(state, conn_id);
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+///the waiting player does not wait anymore. The message is relayed too.
+fn cancel_waiting(state: &mut ServerState, conn_id: usize) {
+    if state
+        .waiting
+        .as_ref()
+        .is_some_and(|waiting| waiting.player.conn_id == conn_id)
+    {
+        state.waiting = None;
+    }
+}
+
+///the first client waits, the second client starts the game with him.
+///The settings from a client are checked before they are used.
+fn join(state: &mut ServerState, player: AuthPlayer, settings: &GameSettings) {
+    if let Err(err) = settings.check() {
+        send_error(state, player, &err.to_string());
+        return;
+    }
+    match state.waiting.take() {
+        Some(waiting) if waiting.player.conn_id != player.conn_id => {
+            let settings = waiting.settings;
+            let engine =
+                GameEngine::new(new_random_cards(settings.card_pairs()), settings.match_rule);
+            let players = [waiting.player, player];
+            for (index, auth_player) in players.iter().enumerate() {
+                let other = players[1 - index];
+                send_to(
+                    state,
+                    auth_player.conn_id,
+                    &WsMessage::AuthStart {
+                        ws_client_instance: auth_player.ws_client_instance,
+                        other_ws_client_instance: other.ws_client_instance,
+                        player_number: index + 1,
+                        settings: settings.clone(),
+                        game_view: engine.view(),
+                    },
+                );
+            }
+            println!(
+                "authoritative game {} vs {}",
+                waiting.player.ws_client_instance, player.ws_client_instance
+            );
+            state.games.push(AuthGame { engine, players });
+        }
+        _ => {
+            state.waiting = Some(AuthWaiting {
+                player,
+                settings: settings.clone(),
+            })
+        }
+    }
+}
+
+///find the game of this player, apply the action and send the result
+fn play<F>(state: &mut ServerState, player: AuthPlayer, action: F)
+where
+    F: FnOnce(&mut GameEngine, usize) -> Result<(), mem2_engine::EngineError>,
+{
+    //the connection must match the ws_client_instance, so nobody can play for somebody else
+    let found = state.games.iter().position(|game| {
+        game.players.iter().any(|auth_player| {
+            auth_player.conn_id == player.conn_id
+                && auth_player.ws_client_instance == player.ws_client_instance
+        })
+    });
+    let game_index = match found {
+        Some(game_index) => game_index,
+        None => {
+            send_error(state, player, "you are not in a server game");
+            return;
+        }
+    };
+    let game = &mut state.games[game_index];
+    let player_number = if game.players[0].conn_id == player.conn_id {
+        1
+    } else {
+        2
+    };
+
+    match action(&mut game.engine, player_number) {
+        Ok(()) => {
+            let players = game.players;
+            let msg = WsMessage::AuthView {
+                vec_ws_client_instance: players.iter().map(|p| p.ws_client_instance).collect(),
+                game_view: game.engine.view(),
+            };
+            let is_game_over = game.engine.is_game_over();
+            for auth_player in &players {
+                send_to(state, auth_player.conn_id, &msg);
+            }
+            if is_game_over {
+                state.games.remove(game_index);
+            }
+        }
+        Err(err) => send_error(state, player, &err.to_string()),
+    }
+}
+
+///only the sender receives the error
+fn send_error(state: &ServerState, player: AuthPlayer, reason: &str) {
+    send_to(
+        state,
+        player.conn_id,
+        &WsMessage::AuthError {
+            ws_client_instance: player.ws_client_instance,
+            reason: reason.to_string(),
+            is_game_over: false,
+        },
+    );
+}
+
+///when a connection closes, its games are over. The other player is told, so he does not wait forever.
+pub fn remove_connection(state: &mut ServerState, conn_id: usize) {
+    if state
+        .waiting
+        .as_ref()
+        .is_some_and(|waiting| waiting.player.conn_id == conn_id)
+    {
+        state.waiting = None;
+    }
+    let vec_survivors: Vec<AuthPlayer> = state
+        .games
+        .iter()
+        .filter(|game| game.players.iter().any(|player| player.conn_id == conn_id))
+        .flat_map(|game| game.players.iter().copied())
+        .filter(|player| player.conn_id != conn_id)
+        .collect();
+    for survivor in vec_survivors {
+        send_to(
+            state,
+            survivor.conn_id,
+            &WsMessage::AuthError {
+                ws_client_instance: survivor.ws_client_instance,
+                reason: "the other player left the game".to_string(),
+                is_game_over: true,
+            },
+        );
+    }
+    state
+        .games
+        .retain(|game| game.players.iter().all(|player| player.conn_id != conn_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+    use std::sync::mpsc::{self, Receiver};
+    use tungstenite::Message;
+
+    ///a server with fake clients. The receivers get what the server sends to each connection.
+    fn server(count_clients: usize) -> (ServerState, Vec<Receiver<Message>>) {
+        let mut clients = Vec::new();
+        let mut receivers = Vec::new();
+        for id in 1..=count_clients {
+            let (sender, receiver) = mpsc::channel();
+            clients.push(Client { id, sender });
+            receivers.push(receiver);
+        }
+        let state = ServerState {
+            clients,
+            waiting: None,
+            games: Vec::new(),
+        };
+        (state, receivers)
+    }
+
+    ///all the messages the connection has received until now
+    fn received(receiver: &Receiver<Message>) -> Vec<WsMessage> {
+        receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                Message::Text(text) => serde_json::from_str(&text).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    ///the first and the second connection start a game
+    fn started_game() -> (ServerState, Vec<Receiver<Message>>) {
+        let (mut state, receivers) = server(2);
+        assert!(handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 11,
+                settings: GameSettings::default(),
+            }
+        ));
+        assert!(handle_auth_message(
+            &mut state,
+            2,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 22,
+                settings: GameSettings::default(),
+            }
+        ));
+        (state, receivers)
+    }
+
+    #[test]
+    fn first_player_waits_for_the_second() {
+        let (mut state, receivers) = server(2);
+        handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 11,
+                settings: GameSettings::default(),
+            },
+        );
+        assert!(state.waiting.is_some());
+        assert!(state.games.is_empty());
+        assert!(received(&receivers[0]).is_empty());
+        handle_auth_message(
+            &mut state,
+            2,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 22,
+                settings: GameSettings::default(),
+            },
+        );
+        assert!(state.waiting.is_none());
+        assert_eq!(state.games.len(), 1);
+        for (index, receiver) in receivers.iter().enumerate() {
+            match received(receiver).as_slice() {
+                [WsMessage::AuthStart {
+                    player_number,
+                    game_view,
+                    ..
+                }] => {
+                    assert_eq!(*player_number, index + 1);
+                    //the cards face down are not sent
+                    assert!(game_view
+                        .vec_cards
+                        .iter()
+                        .all(|card| card.card_number_and_img_src == 0));
+                }
+                _ => panic!("no AuthStart"),
+            }
+        }
+    }
+
+    #[test]
+    fn game_uses_the_settings_of_the_waiting_player() {
+        let (mut state, receivers) = server(2);
+        let settings = GameSettings {
+            grid_columns: 6,
+            grid_rows: 4,
+            ..GameSettings::default()
+        };
+        handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 11,
+                settings: settings.clone(),
+            },
+        );
+        handle_auth_message(
+            &mut state,
+            2,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 22,
+                settings: GameSettings::default(),
+            },
+        );
+        for receiver in &receivers {
+            match received(receiver).as_slice() {
+                [WsMessage::AuthStart {
+                    settings: received_settings,
+                    game_view,
+                    ..
+                }] => {
+                    assert_eq!(*received_settings, settings);
+                    //card 0 is only a placeholder
+                    assert_eq!(game_view.vec_cards.len(), 6 * 4 + 1);
+                }
+                _ => panic!("no AuthStart"),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let (mut state, receivers) = server(1);
+        handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 11,
+                settings: GameSettings {
+                    grid_columns: 5,
+                    grid_rows: 5,
+                    ..GameSettings::default()
+                },
+            },
+        );
+        assert!(state.waiting.is_none());
+        assert!(matches!(
+            received(&receivers[0]).as_slice(),
+            [WsMessage::AuthError {
+                ws_client_instance: 11,
+                is_game_over: false,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn same_connection_does_not_play_against_itself() {
+        let (mut state, _receivers) = server(1);
+        for _ in 0..2 {
+            handle_auth_message(
+                &mut state,
+                1,
+                &WsMessage::AuthJoin {
+                    ws_client_instance: 11,
+                    settings: GameSettings::default(),
+                },
+            );
+        }
+        assert!(state.waiting.is_some());
+        assert!(state.games.is_empty());
+    }
+
+    #[test]
+    fn flip_on_the_other_players_turn_is_rejected() {
+        let (mut state, receivers) = started_game();
+        received(&receivers[0]);
+        received(&receivers[1]);
+        handle_auth_message(
+            &mut state,
+            2,
+            &WsMessage::AuthFlip {
+                ws_client_instance: 22,
+                card_index: 1,
+            },
+        );
+        assert!(matches!(
+            received(&receivers[1]).as_slice(),
+            [WsMessage::AuthError {
+                ws_client_instance: 22,
+                ..
+            }]
+        ));
+        //only the sender gets the error
+        assert!(received(&receivers[0]).is_empty());
+    }
+
+    #[test]
+    fn flip_of_the_player_whose_turn_it_is_goes_to_both() {
+        let (mut state, receivers) = started_game();
+        received(&receivers[0]);
+        received(&receivers[1]);
+        handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthFlip {
+                ws_client_instance: 11,
+                card_index: 1,
+            },
+        );
+        for receiver in &receivers {
+            match received(receiver).as_slice() {
+                [WsMessage::AuthView { game_view, .. }] => {
+                    assert_ne!(game_view.vec_cards[1].card_number_and_img_src, 0);
+                    assert_eq!(game_view.vec_cards[2].card_number_and_img_src, 0);
+                }
+                _ => panic!("no AuthView"),
+            }
+        }
+    }
+
+    #[test]
+    fn nobody_can_play_for_another_client() {
+        let (mut state, receivers) = started_game();
+        received(&receivers[1]);
+        //the second connection pretends to be the first player
+        handle_auth_message(
+            &mut state,
+            2,
+            &WsMessage::AuthFlip {
+                ws_client_instance: 11,
+                card_index: 1,
+            },
+        );
+        assert!(matches!(
+            received(&receivers[1]).as_slice(),
+            [WsMessage::AuthError { .. }]
+        ));
+    }
+
+    #[test]
+    fn disconnect_ends_the_game() {
+        let (mut state, receivers) = started_game();
+        received(&receivers[0]);
+        remove_connection(&mut state, 2);
+        assert!(state.games.is_empty());
+        //the other player knows the game is over
+        assert!(matches!(
+            received(&receivers[0]).as_slice(),
+            [WsMessage::AuthError {
+                ws_client_instance: 11,
+                is_game_over: true,
+                ..
+            }]
+        ));
+        handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthFlip {
+                ws_client_instance: 11,
+                card_index: 1,
+            },
+        );
+        assert!(matches!(
+            received(&receivers[0]).as_slice(),
+            [WsMessage::AuthError {
+                is_game_over: false,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn disconnect_of_the_waiting_player_clears_the_waiting() {
+        let (mut state, _receivers) = server(2);
+        handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 11,
+                settings: GameSettings::default(),
+            },
+        );
+        remove_connection(&mut state, 1);
+        assert!(state.waiting.is_none());
+        //the next player waits, he does not start a game with the gone one
+        handle_auth_message(
+            &mut state,
+            2,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 22,
+                settings: GameSettings::default(),
+            },
+        );
+        assert!(state.games.is_empty());
+        assert!(state.waiting.is_some());
+    }
+
+    #[test]
+    fn room_sealed_cancel_clears_the_waiting() {
+        let (mut state, _receivers) = server(2);
+        handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::AuthJoin {
+                ws_client_instance: 11,
+                settings: GameSettings::default(),
+            },
+        );
+        let cancel = mem2_engine::RoomKey::derive("room")
+            .seal(WsMessage::CancelInvite {
+                ws_client_instance: 11,
+            })
+            .unwrap();
+        //another connection cannot cancel for the waiting player
+        assert!(!handle_auth_message(&mut state, 2, &cancel));
+        assert!(state.waiting.is_some());
+        //the cancel is relayed too
+        assert!(!handle_auth_message(&mut state, 1, &cancel));
+        assert!(state.waiting.is_none());
+    }
+
+    #[test]
+    fn server_messages_from_clients_are_dropped() {
+        let (mut state, receivers) = started_game();
+        received(&receivers[0]);
+        let forged = WsMessage::AuthView {
+            vec_ws_client_instance: vec![11, 22],
+            game_view: state.games[0].engine.view(),
+        };
+        //handled means it is not relayed
+        assert!(handle_auth_message(&mut state, 2, &forged));
+        assert!(received(&receivers[0]).is_empty());
+    }
+
+    #[test]
+    fn other_messages_are_relayed() {
+        let (mut state, _receivers) = server(1);
+        assert!(!handle_auth_message(
+            &mut state,
+            1,
+            &WsMessage::PlayerChange {
+                ws_client_instance: 11
+            }
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+//! mem2_relay - a small local http + WebSocket relay server for development and tests.
+//! It serves the static files of the game: index.html, favicon.ico, css/, content/, content02/, themes/ and pkg/.
+//! On the path mem2ws/ it accepts WebSocket connections and relays every message to all other clients.
+//! The messages of the server-authoritative mode are not relayed. The server plays the game rules, read authoritative.rs.
+//! The same address and port serve both, because the client builds the WebSocket url from the page url.
+//! It is not a production server. For that read about mem2_server in Readme.md.
+//! Run it from the mem2 folder after `wasm-pack build --target web`:
+//! `cargo run -p mem2_relay`
+//! Optional arguments are the port and the folder with the static files:
+//! `cargo run -p mem2_relay -- 4000 .`
+
+//region: use statements
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::Message;
+
+mod authoritative;
+use authoritative::{AuthGame, AuthWaiting};
+use mem2_engine::WsMessage;
+//endregion
+
+//region: const and structs
+///the default port, if there is no argument
+const DEFAULT_PORT: &str = "4000";
+///the path of the WebSocket, the client adds it in setup_ws_connection
+const WS_PATH: &str = "/mem2ws";
+///how long the WebSocket read waits, before it checks if there are messages to relay
+const WS_READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+///one connected WebSocket client
+struct Client {
+    ///unique id of the connection
+    id: usize,
+    ///the messages from other clients are sent to this channel
+    sender: Sender<Message>,
+}
+
+///everything the server remembers
+pub struct ServerState {
+    ///all connected WebSocket clients
+    clients: Vec<Client>,
+    ///the client that waits for another client to start a server-authoritative game
+    waiting: Option<AuthWaiting>,
+    ///server-authoritative games in progress
+    games: Vec<AuthGame>,
+}
+
+///the state is shared by all connection threads
+type Shared = Arc<Mutex<ServerState>>;
+//endregion
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let port = args.next().unwrap_or_else(|| DEFAULT_PORT.to_string());
+    let root = PathBuf::from(args.next().unwrap_or_else(|| ".".to_string()));
+
+    let listener = TcpListener::bind(format!("0.0.0.0:{}", port)).expect("cannot bind the port");
+    println!("mem2_relay serves files from {}", root.display());
+    println!("Open http://localhost:{}/ in 2 browser windows.", port);
+
+    let shared: Shared = Arc::new(Mutex::new(ServerState {
+        clients: Vec::new(),
+        waiting: None,
+        games: Vec::new(),
+    }));
+    for (id, stream) in listener.incoming().enumerate() {
+        match stream {
+            Ok(stream) => {
+                let shared = Arc::clone(&shared);
+                let root = root.clone();
+                thread::spawn(move || {
+                    if let Err(err) = handle_connection(stream, &root, &shared, id) {
+                        println!("connection {} error: {}", id, err);
+                    }
+                });
+            }
+            Err(err) => println!("accept error: {}", err),
+        }
+    }
+}
+
+///look at the request line without reading it, then choose WebSocket or static file
+fn handle_connection(stream: TcpStream, root: &Path, shared: &Shared, id: usize) -> io::Result<()> {
+    let mut buf = [0_u8; 1024];
+    let len = stream.peek(&mut buf)?;
+    let request = String::from_utf8_lossy(&buf[..len]);
+    //the request line looks like: GET /path HTTP/1.1
+    let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    if path.starts_with(WS_PATH) {
+        relay_websocket(stream, shared, id)
+    } else {
+        serve_file(stream, root, &path)
+    }
+}
+
+//region: static files
+///the browser needs the right content type, especially for wasm
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()).unwrap_or("") {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css",
+        "js" => "application/javascript",
+        "wasm" => "application/wasm",
+        "json" => "application/json",
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "mp3" => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+///only simple GET requests are needed for the game
+fn serve_file(stream: TcpStream, root: &Path, path: &str) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    //read the request headers until the empty line
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let mut stream = reader.into_inner();
+
+    //the query is used by the client to choose the transport, not by the server
+    let path = path.split('?').next().unwrap_or("/");
+    let relative = if path == "/" {
+        "index.html"
+    } else {
+        path.trim_start_matches('/')
+    };
+    //never serve files outside of the root folder
+    if relative.split('/').any(|part| part == "..") {
+        return write_response(&mut stream, "403 Forbidden", "text/plain", b"Forbidden");
+    }
+    let file_path = root.join(relative);
+    match fs::read(&file_path) {
+        Ok(body) => write_response(&mut stream, "200 OK", content_type(&file_path), &body),
+        Err(_) => write_response(&mut stream, "404 Not Found", "text/plain", b"Not Found"),
+    }
+}
+
+///http response with the body
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+//endregion
+
+//region: WebSocket relay
+///every message from this client is sent to all other clients
+fn relay_websocket(stream: TcpStream, shared: &Shared, id: usize) -> io::Result<()> {
+    let mut ws = tungstenite::accept(stream).map_err(|err| io::Error::other(err.to_string()))?;
+    ws.get_ref().set_read_timeout(Some(WS_READ_TIMEOUT))?;
+
+    let (sender, receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+    lock(shared).clients.push(Client { id, sender });
+    println!("client {} connected", id);
+
+    loop {
+        match ws.read() {
+            Ok(msg @ Message::Text(_)) | Ok(msg @ Message::Binary(_)) => {
+                let mut state = lock(shared);
+                let is_handled = match msg {
+                    Message::Text(ref text) => {
+                        serde_json::from_str::<WsMessage>(text).is_ok_and(|ws_message| {
+                            authoritative::handle_auth_message(&mut state, id, &ws_message)
+                        })
+                    }
+                    Message::Binary(ref bytes) => {
+                        WsMessage::from_binary(bytes).is_ok_and(|ws_message| {
+                            authoritative::handle_auth_message(&mut state, id, &ws_message)
+                        })
+                    }
+                    _ => false,
+                };
+                if !is_handled {
+                    for client in state.clients.iter().filter(|client| client.id != id) {
+                        //the other client can be already disconnected, that is not an error
+                        let _ = client.sender.send(msg.clone());
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(ref err))
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+        //send what the other clients have sent in the meantime
+        while let Ok(msg) = receiver.try_recv() {
+            if ws.send(msg).is_err() {
+                break;
+            }
+        }
+    }
+
+    let mut state = lock(shared);
+    state.clients.retain(|client| client.id != id);
+    authoritative::remove_connection(&mut state, id);
+    drop(state);
+    println!("client {} disconnected", id);
+    Ok(())
+}
+
+///send a message only to one connection
+pub fn send_to(state: &ServerState, conn_id: usize, ws_message: &WsMessage) {
+    if let Some(client) = state.clients.iter().find(|client| client.id == conn_id) {
+        if let Ok(text) = serde_json::to_string(ws_message) {
+            let _ = client.sender.send(Message::Text(text));
+        }
+    }
+}
+
+///a thread that panicked while holding the lock must not stop the whole server
+fn lock(shared: &Shared) -> std::sync::MutexGuard<'_, ServerState> {
+    shared
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+//endregion
@@ -0,0 +1,95 @@
+//! accessibility.rs - the card grid for the keyboard and the screen reader.
+//! The arrow keys move the focus across the grid, Enter or Space flips the focused card.
+//! The next card of a key comes from `next_card_index` in mem2_engine.
+//! Only the focused card is in the tab order, so the Tab key jumps over the grid in one step.
+//! Every card has a label with its state and, when it is face up, its letter.
+//! The turns, the pairs and the scores are announced in a polite live region.
+
+use crate::{GameData, Spelling};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use mem2_engine::{morse, Card, CardStatusCardFace};
+use wasm_bindgen::JsCast;
+
+///the letter and its spelling word like "A alpha". Until the spelling is loaded only the letter.
+pub fn letter_and_word(card_number: usize, spelling: Option<&Spelling>) -> String {
+    let letter = morse::card_letter(card_number).unwrap_or(' ');
+    spelling
+        .and_then(|spelling| spelling.name.get(card_number))
+        .map_or_else(|| letter.to_string(), |word| format!("{} {}", letter, word))
+}
+
+///the alt text and aria-label of the card. The face down card does not tell its letter.
+pub fn card_label(card: &Card, spelling: Option<&Spelling>) -> String {
+    match card.status {
+        CardStatusCardFace::Down => format!("card {}, face down", card.card_index_and_id),
+        CardStatusCardFace::UpTemporary => format!(
+            "card {}, {}, face up",
+            card.card_index_and_id,
+            letter_and_word(card.card_number_and_img_src, spelling)
+        ),
+        CardStatusCardFace::UpPermanently => format!(
+            "card {}, {}, matched",
+            card.card_index_and_id,
+            letter_and_word(card.card_number_and_img_src, spelling)
+        ),
+    }
+}
+
+///move the focus of the browser to the card. The id of the image is like "img07".
+pub fn focus_card(card_index: usize) {
+    if let Some(element) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(&format!("img{:02}", card_index)))
+        .and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok())
+    {
+        let _ = element.focus();
+    }
+}
+
+///the scores of both players
+pub fn score_text(game_data: &GameData) -> String {
+    format!(
+        "Score: player1 {}, player2 {}.",
+        game_data.player1_points, game_data.player2_points
+    )
+}
+
+///whose turn it is
+pub fn turn_text(game_data: &GameData) -> String {
+    if game_data.player_turn == game_data.this_machine_player_number {
+        format!("Player{}, it is your turn.", game_data.player_turn)
+    } else {
+        format!("It is the turn of player{}.", game_data.player_turn)
+    }
+}
+
+///the text for the live region after the second card of the turn
+pub fn pair_text(game_data: &GameData, card_number: usize, is_match: bool) -> String {
+    let spelling = crate::get_spelling().ok();
+    if !is_match {
+        return "No match.".to_string();
+    }
+    let mut announcement = format!(
+        "Match: {}. {}",
+        letter_and_word(card_number, spelling.as_ref()),
+        score_text(game_data)
+    );
+    if game_data.is_game_over() {
+        announcement.push_str(" All pairs are found. Game over.");
+    }
+    announcement
+}
+
+///the invisible live region. The screen reader reads its text when it changes.
+pub fn div_live_region<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    div(bump)
+        .attr("class", "visually_hidden")
+        .attr("role", "status")
+        .attr("aria-live", "polite")
+        .children([text(
+            bumpalo::format!(in bump, "{}", game_data.announcement).into_bump_str(),
+        )])
+        .finish()
+}
@@ -0,0 +1,325 @@
+//! audio.rs - the Morse sounds of the cards.
+//! The Morse code of the card letter is synthesized by the tone generator with the speed and pitch of the player.
+//! Browsers without Web Audio play the prerecorded mp3 files of the deck instead.
+//! The mp3 sounds are preloaded when the deck is chosen, so the first flips do not wait for the download.
+//! Every mp3 sound has one cached audio element that is played again from the start.
+//! The browser can refuse to play before the first user interaction. The rejected Promise is only logged.
+//! Mute, volume, speed and pitch are saved in localStorage.
+
+use crate::error::{Mem2Error, Mem2Result};
+use crate::indicator::{self, Status};
+use crate::tone::ToneGenerator;
+use crate::{local_storage, GameData, RootRenderingComponent};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use futures::Future;
+use mem2_engine::{morse, CARD_NUMBER_MAX};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{console, HtmlAudioElement};
+
+///the name of the mute item in localStorage
+const AUDIO_MUTED_STORAGE_KEY: &str = "mem2_audio_muted";
+///the name of the volume item in localStorage
+const AUDIO_VOLUME_STORAGE_KEY: &str = "mem2_audio_volume";
+///the name of the Morse speed item in localStorage
+const AUDIO_WPM_STORAGE_KEY: &str = "mem2_audio_wpm";
+///the name of the Morse pitch item in localStorage
+const AUDIO_PITCH_STORAGE_KEY: &str = "mem2_audio_pitch";
+///every click on the volume goes to the next step
+const VOLUME_STEPS: &[f64] = &[0.25, 0.5, 0.75, 1.0];
+///the Morse speeds in words per minute
+const WPM_STEPS: &[u32] = &[5, 10, 15, 20, 25];
+///the slow speed is easier to learn
+const DEFAULT_WPM: u32 = 10;
+///the Morse pitches in Hz
+const PITCH_STEPS: &[u32] = &[400, 550, 700, 850];
+///the usual pitch of Morse code
+const DEFAULT_PITCH_HZ: u32 = 700;
+
+///the cached sounds of one deck and the player's audio settings
+pub struct AudioManager {
+    ///synthesized Morse. None if the browser has no Web Audio.
+    tone_generator: Option<ToneGenerator>,
+    ///the content folder of the cached sounds
+    deck: String,
+    ///one audio element for every card number. Index 0 is the card face down and has no sound.
+    vec_elements: Vec<Option<HtmlAudioElement>>,
+    ///no sound at all
+    pub is_muted: bool,
+    ///from 0.0 to 1.0
+    pub volume: f64,
+    ///the Morse speed in words per minute
+    pub wpm: u32,
+    ///the Morse pitch in Hz
+    pub pitch_hz: u32,
+}
+
+impl AudioManager {
+    ///the audio settings from localStorage. The sounds are not loaded yet.
+    pub fn load() -> Self {
+        let storage = local_storage();
+        let get_item = |key: &str| {
+            storage
+                .as_ref()
+                .and_then(|storage| storage.get_item(key).ok())
+                .and_then(|opt| opt)
+        };
+        let is_muted = get_item(AUDIO_MUTED_STORAGE_KEY).map_or(false, |muted| muted == "true");
+        let volume = get_item(AUDIO_VOLUME_STORAGE_KEY)
+            .and_then(|volume| volume.parse::<f64>().ok())
+            .filter(|volume| *volume >= 0.0 && *volume <= 1.0)
+            .unwrap_or(1.0);
+        let get_step = |key: &str, steps: &[u32], default: u32| {
+            get_item(key)
+                .and_then(|step| step.parse::<u32>().ok())
+                .filter(|step| steps.contains(step))
+                .unwrap_or(default)
+        };
+        AudioManager {
+            tone_generator: if ToneGenerator::is_supported() {
+                Some(ToneGenerator::new())
+            } else {
+                None
+            },
+            deck: String::new(),
+            vec_elements: Vec::new(),
+            is_muted,
+            volume,
+            wpm: get_step(AUDIO_WPM_STORAGE_KEY, WPM_STEPS, DEFAULT_WPM),
+            pitch_hz: get_step(AUDIO_PITCH_STORAGE_KEY, PITCH_STEPS, DEFAULT_PITCH_HZ),
+        }
+    }
+
+    ///the url of the mp3 of the card number
+    fn sound_src(deck: &str, card_number: usize) -> String {
+        format!("{}/sound/mem_sound_{:02}.mp3", deck, card_number)
+    }
+
+    ///start the download of all the mp3 sounds of the deck. The old deck is forgotten.
+    ///The synthesized Morse does not need them.
+    pub fn preload(&mut self, deck: &str) -> Mem2Result<()> {
+        if self.tone_generator.is_some() || self.deck == deck {
+            return Ok(());
+        }
+        let mut vec_elements = vec![None];
+        for card_number in 1..=CARD_NUMBER_MAX {
+            let element = HtmlAudioElement::new_with_src(&Self::sound_src(deck, card_number))
+                .map_err(|err| Mem2Error::Audio(Mem2Error::js_message(&err)))?;
+            element.set_preload("auto");
+            element.load();
+            vec_elements.push(Some(element));
+        }
+        self.deck = deck.to_string();
+        self.vec_elements = vec_elements;
+        Ok(())
+    }
+
+    ///play the Morse code of the card letter from the start. A sound that is still playing stops.
+    pub fn play(&mut self, deck: &str, card_number: usize) -> Mem2Result<()> {
+        if self.is_muted {
+            return Ok(());
+        }
+        if self.tone_generator.is_some() {
+            let letter =
+                morse::card_letter(card_number).ok_or(Mem2Error::CardIndex(card_number))?;
+            return self.play_text(&letter.to_string());
+        }
+        self.preload(deck)?;
+        let element = self
+            .vec_elements
+            .get(card_number)
+            .and_then(Option::as_ref)
+            .ok_or(Mem2Error::CardIndex(card_number))?;
+        element.set_volume(self.volume);
+        element.set_current_time(0.0);
+        let promise = element
+            .play()
+            .map_err(|err| Mem2Error::Audio(Mem2Error::js_message(&err)))?;
+        //the autoplay policy rejects the play before the first user interaction. The game goes on without sound.
+        wasm_bindgen_futures::spawn_local(JsFuture::from(promise).map(|_| ()).map_err(|err| {
+            console::log_1(&format!("sound not played: {}", Mem2Error::js_message(&err)).into());
+        }));
+        Ok(())
+    }
+
+    ///play the Morse code of any text with the speed and pitch of the player.
+    ///Without Web Audio there is no sound.
+    pub fn play_text(&mut self, text: &str) -> Mem2Result<()> {
+        if self.is_muted {
+            return Ok(());
+        }
+        match self.tone_generator.as_mut() {
+            Some(tone_generator) => {
+                tone_generator.play_text(text, self.wpm, self.pitch_hz, self.volume)
+            }
+            None => Ok(()),
+        }
+    }
+
+    ///how long the sound of the card lasts. The mp3 knows it after the download, else it is 0.
+    pub fn sound_duration_ms(&self, card_number: usize) -> f64 {
+        if self.tone_generator.is_some() {
+            morse::card_letter(card_number).map_or(0.0, |letter| {
+                morse::duration_ms(&morse::tones(&letter.to_string(), self.wpm))
+            })
+        } else {
+            self.vec_elements
+                .get(card_number)
+                .and_then(Option::as_ref)
+                .map(|element| element.duration())
+                .filter(|duration| duration.is_finite())
+                .map_or(0.0, |duration| duration * 1000.0)
+        }
+    }
+
+    ///mute or unmute and save it
+    pub fn toggle_mute(&mut self) {
+        self.is_muted = !self.is_muted;
+        if self.is_muted {
+            if let Some(tone_generator) = self.tone_generator.as_mut() {
+                tone_generator.stop();
+            }
+        }
+        save_item(
+            AUDIO_MUTED_STORAGE_KEY,
+            if self.is_muted { "true" } else { "false" },
+        );
+    }
+
+    ///the next volume step and save it. After the loudest comes the quietest.
+    pub fn next_volume(&mut self) {
+        let position = VOLUME_STEPS.iter().position(|step| *step >= self.volume);
+        let next = position.map_or(0, |position| (position + 1) % VOLUME_STEPS.len());
+        self.volume = VOLUME_STEPS.get(next).copied().unwrap_or(1.0);
+        save_item(AUDIO_VOLUME_STORAGE_KEY, &self.volume.to_string());
+    }
+
+    ///the next Morse speed and save it. After the fastest comes the slowest.
+    pub fn next_wpm(&mut self) {
+        self.wpm = next_step(WPM_STEPS, self.wpm);
+        save_item(AUDIO_WPM_STORAGE_KEY, &self.wpm.to_string());
+    }
+
+    ///the next Morse pitch and save it. After the highest comes the lowest.
+    pub fn next_pitch(&mut self) {
+        self.pitch_hz = next_step(PITCH_STEPS, self.pitch_hz);
+        save_item(AUDIO_PITCH_STORAGE_KEY, &self.pitch_hz.to_string());
+    }
+
+    ///speed and pitch only change the synthesized Morse
+    pub fn is_synthesized(&self) -> bool {
+        self.tone_generator.is_some()
+    }
+}
+
+///the step after the current one. An unknown current value goes to the first step.
+fn next_step(steps: &[u32], current: u32) -> u32 {
+    let next = steps
+        .iter()
+        .position(|step| *step == current)
+        .map_or(0, |position| (position + 1) % steps.len());
+    steps.get(next).copied().unwrap_or(current)
+}
+
+///write one audio setting to localStorage
+fn save_item(key: &str, value: &str) {
+    if let Some(storage) = local_storage() {
+        if storage.set_item(key, value).is_err() {
+            console::log_1(&"error: cannot save the audio settings to localStorage".into());
+        }
+    }
+}
+
+///sound on/off, the volume and the Morse speed and pitch. Click to change.
+pub fn div_audio_settings<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    let audio = &game_data.audio;
+    let sound_status = if audio.is_muted {
+        Status::Negative
+    } else {
+        Status::Positive
+    };
+    let mut vec_settings = vec![
+        div(bump)
+            .attr("class", "grid_item")
+            .attr("style", indicator::style(game_data, sound_status, bump))
+            .children([text(indicator::label(
+                game_data,
+                sound_status,
+                if audio.is_muted {
+                    "sound: off"
+                } else {
+                    "sound: on"
+                },
+                bump,
+            ))])
+            .on("click", move |root, vdom, _event| {
+                let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                root_rendering_component.rc.borrow_mut().audio.toggle_mute();
+                vdom.schedule_render();
+            })
+            .finish(),
+        div(bump)
+            .attr("class", "grid_item")
+            .attr("style", indicator::style(game_data, Status::Action, bump))
+            .children([text(
+                bumpalo::format!(in bump, "volume: {:.0}%", audio.volume * 100.0).into_bump_str(),
+            )])
+            .on("click", move |root, vdom, _event| {
+                let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                root_rendering_component.rc.borrow_mut().audio.next_volume();
+                vdom.schedule_render();
+            })
+            .finish(),
+    ];
+    if audio.is_synthesized() {
+        vec_settings.push(
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, Status::Action, bump))
+                .children([text(
+                    bumpalo::format!(in bump, "speed: {} wpm", audio.wpm).into_bump_str(),
+                )])
+                .on("click", move |root, vdom, _event| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    game_data.audio.next_wpm();
+                    //the player hears the new speed with the standard word of the speed
+                    if let Err(err) = game_data.audio.play_text("PARIS") {
+                        game_data.show_error(err);
+                    }
+                    vdom.schedule_render();
+                })
+                .finish(),
+        );
+        vec_settings.push(
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, Status::Action, bump))
+                .children([text(
+                    bumpalo::format!(in bump, "pitch: {} Hz", audio.pitch_hz).into_bump_str(),
+                )])
+                .on("click", move |root, vdom, _event| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    game_data.audio.next_pitch();
+                    //the player hears the new pitch
+                    if let Err(err) = game_data.audio.play_text("E") {
+                        game_data.show_error(err);
+                    }
+                    vdom.schedule_render();
+                })
+                .finish(),
+        );
+    }
+    let grid_template_columns = if audio.is_synthesized() {
+        "grid-template-columns: auto auto auto auto;"
+    } else {
+        "grid-template-columns: auto auto;"
+    };
+    div(bump)
+        .attr("class", "grid_container_players")
+        .attr("style", grid_template_columns)
+        .children(vec_settings)
+        .finish()
+}
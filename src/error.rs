@@ -0,0 +1,144 @@
+//! error.rs - one error type for the whole crate instead of panics.
+//! The errors at startup (no window, no `#div_for_virtual_dom`, no connection) stop the game gracefully:
+//! `run()` returns them to javascript and they are written in the console.
+//! All other errors are recoverable. They are shown in the `ErrorOverlay` and the player can dismiss them.
+
+use crate::RootRenderingComponent;
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::{Node, Render};
+use mem2_engine::{EngineError, IllegalTransition};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsValue;
+
+///all the errors of mem2
+#[derive(Clone, PartialEq, Eq)]
+pub enum Mem2Error {
+    ///there is no browser window
+    NoWindow,
+    ///the window has no document
+    NoDocument,
+    ///the html element with this id is missing
+    ElementNotFound(String),
+    ///sessionStorage or localStorage is disabled in the browser
+    StorageUnavailable,
+    ///the spelling from text.json is not (yet) in sessionStorage
+    SpellingNotLoaded,
+    ///cannot connect, or cannot send because the connection is closed
+    Transport(String),
+    ///json serialization or parsing
+    Json(String),
+    ///the audio cannot be created or played
+    Audio(String),
+    ///the timer cannot be started
+    Timer(String),
+    ///there is no card with this index
+    CardIndex(usize),
+    ///the game rules do not allow this action
+    Rules(EngineError),
+    ///the event is not allowed in the current game state
+    State(IllegalTransition),
+    ///the server rejected the action or ended the game
+    Server(String),
+}
+
+///the result type used by the game and transport layers
+pub type Mem2Result<T> = Result<T, Mem2Error>;
+
+///Render Component: the recoverable errors over the game
+pub struct ErrorOverlay {
+    ///shared mutable data
+    pub rc: Rc<RefCell<crate::GameData>>,
+}
+
+impl std::fmt::Display for Mem2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mem2Error::NoWindow => write!(f, "there is no browser window"),
+            Mem2Error::NoDocument => write!(f, "the window has no document"),
+            Mem2Error::ElementNotFound(id) => write!(f, "no element #{}", id),
+            Mem2Error::StorageUnavailable => write!(f, "the browser storage is not available"),
+            Mem2Error::SpellingNotLoaded => write!(f, "the spelling is not loaded"),
+            Mem2Error::Transport(msg) => write!(f, "connection: {}", msg),
+            Mem2Error::Json(msg) => write!(f, "json: {}", msg),
+            Mem2Error::Audio(msg) => write!(f, "audio: {}", msg),
+            Mem2Error::Timer(msg) => write!(f, "timer: {}", msg),
+            Mem2Error::CardIndex(index) => write!(f, "there is no card with index {}", index),
+            Mem2Error::Rules(err) => write!(f, "{}", err),
+            Mem2Error::State(err) => write!(f, "{}", err),
+            Mem2Error::Server(reason) => write!(f, "server: {}", reason),
+        }
+    }
+}
+
+impl Mem2Error {
+    ///JsValue errors from web-sys have mostly a message inside
+    pub fn js_message(js_value: &JsValue) -> String {
+        js_value
+            .as_string()
+            .or_else(|| {
+                js_sys::Reflect::get(js_value, &"message".into())
+                    .ok()
+                    .and_then(|msg| msg.as_string())
+            })
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+impl From<serde_json::Error> for Mem2Error {
+    fn from(err: serde_json::Error) -> Self {
+        Mem2Error::Json(err.to_string())
+    }
+}
+
+impl From<EngineError> for Mem2Error {
+    fn from(err: EngineError) -> Self {
+        Mem2Error::Rules(err)
+    }
+}
+
+impl From<IllegalTransition> for Mem2Error {
+    fn from(err: IllegalTransition) -> Self {
+        Mem2Error::State(err)
+    }
+}
+
+///`run()` returns the error to javascript
+impl From<Mem2Error> for JsValue {
+    fn from(err: Mem2Error) -> Self {
+        JsValue::from_str(&err.to_string())
+    }
+}
+
+impl Render for ErrorOverlay {
+    ///the overlay is visible only when there are errors. A click dismisses all of them.
+    fn render<'a, 'bump>(&'a self, bump: &'bump Bump) -> Node<'bump>
+    where
+        'a: 'bump,
+    {
+        //this game_data mutable reference is dropped on the end of the function
+        let game_data = self.rc.borrow();
+        if game_data.vec_errors.is_empty() {
+            return div(bump).finish();
+        }
+        let mut vec_children = Vec::new();
+        for err in &game_data.vec_errors {
+            vec_children.push(
+                h5(bump)
+                    .children([text(bumpalo::format!(in bump, "{}", err).into_bump_str())])
+                    .finish(),
+            );
+        }
+        vec_children.push(h6(bump).children([text("Click to dismiss.")]).finish());
+        div(bump)
+            .attr("class", "error_overlay")
+            .children(vec_children)
+            .on("click", move |root, vdom, _event| {
+                let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                root_rendering_component.rc.borrow_mut().vec_errors.clear();
+                vdom.schedule_render();
+            })
+            .finish()
+    }
+}
@@ -0,0 +1,134 @@
+//! high_scores.rs - local table of the best finished games.
+//! The table is saved in the browser localStorage, so it stays only on this device.
+//! The ranking is in mem2_engine: one table for every game mode, grid size, deck and time limit,
+//! the fewest clicks first and then the shorter time.
+
+use crate::error::Mem2Error;
+use crate::indicator::{self, Status};
+use crate::{local_storage, GameData, RootRenderingComponent};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use mem2_engine::HighScores;
+use web_sys::console;
+
+///the name of the item in localStorage
+const HIGH_SCORES_STORAGE_KEY: &str = "mem2_high_scores";
+
+///read from localStorage. If there is nothing or it cannot be parsed, start with an empty table.
+pub fn load() -> HighScores {
+    local_storage()
+        .and_then(|storage| storage.get_item(HIGH_SCORES_STORAGE_KEY).ok())
+        .and_then(|opt| opt)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+///write to localStorage
+pub fn save(high_scores: &HighScores) {
+    if let Some(storage) = local_storage() {
+        if storage
+            .set_item(HIGH_SCORES_STORAGE_KEY, &to_json(high_scores))
+            .is_err()
+        {
+            console::log_1(&"error: cannot save high scores to localStorage".into());
+        }
+    }
+}
+
+///all the tables as json for export
+pub fn to_json(high_scores: &HighScores) -> String {
+    serde_json::to_string(high_scores).unwrap_or_default()
+}
+
+///import the tables from json and merge them with the existing entries
+pub fn merge_json(high_scores: &mut HighScores, json: &str) -> Result<(), serde_json::Error> {
+    let imported: HighScores = serde_json::from_str(json)?;
+    high_scores.merge(imported);
+    Ok(())
+}
+
+///the high score table for the current game. It is shown after the game is finished.
+pub fn div_high_scores<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    let key = game_data.high_score_key();
+    let mut vec_rows = Vec::new();
+    vec_rows.push(
+        h4(bump)
+            .children([text(
+                bumpalo::format!(in bump, "High scores {} {} {}{}", key.game_mode, key.grid_size, key.deck,
+                    if key.time_limit_s > 0 { format!(" {}s", key.time_limit_s) } else { String::new() })
+                    .into_bump_str(),
+            )])
+            .finish(),
+    );
+    for (rank, entry) in game_data.high_scores.entries(&key).iter().enumerate() {
+        //the results of this game are underlined
+        let style = if game_data.vec_high_score_ranks.contains(&rank) {
+            "text-decoration:underline;"
+        } else {
+            ""
+        };
+        vec_rows.push(
+            h5(bump)
+                .attr("style", style)
+                .children([text(
+                    bumpalo::format!(in bump, "{}. {}clicks: {} time: {:.1}s points: {} {}",
+                        rank + 1,
+                        if entry.player_number == 0 { String::new() } else { format!("player{} ", entry.player_number) },
+                        entry.count_all_clicks,
+                        entry.duration_ms / 1000.0,
+                        entry.points,
+                        entry.date.get(..10).unwrap_or(""))
+                    .into_bump_str(),
+                )])
+                .finish(),
+        );
+    }
+    vec_rows.push(
+        div(bump)
+            .attr("class", "grid_container_players")
+            .attr("style", "grid-template-columns: auto auto;")
+            .children([
+                div(bump)
+                    .attr("class", "grid_item")
+                    .attr("style", indicator::style(game_data, Status::Action, bump))
+                    .children([text("Export")])
+                    .on("click", move |root, _vdom, _event| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        let game_data = root_rendering_component.rc.borrow();
+                        //the prompt is the simplest way to show a text the user can copy
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.prompt_with_message_and_default(
+                                "Copy the high scores:",
+                                &to_json(&game_data.high_scores),
+                            );
+                        }
+                    })
+                    .finish(),
+                div(bump)
+                    .attr("class", "grid_item")
+                    .attr("style", indicator::style(game_data, Status::Action, bump))
+                    .children([text("Import")])
+                    .on("click", move |root, vdom, _event| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        let window = match web_sys::window() {
+                            Some(window) => window,
+                            None => return,
+                        };
+                        if let Ok(Some(json)) =
+                            window.prompt_with_message("Paste the exported high scores:")
+                        {
+                            match merge_json(&mut game_data.high_scores, &json) {
+                                Ok(()) => save(&game_data.high_scores),
+                                Err(err) => game_data.show_error(Mem2Error::from(err)),
+                            }
+                            vdom.schedule_render();
+                        }
+                    })
+                    .finish(),
+            ])
+            .finish(),
+    );
+    div(bump).children(vec_rows).finish()
+}
@@ -0,0 +1,207 @@
+//! indicator.rs - the colours and the icons of the status on the screen.
+//! The standard colours come from the palette of the theme. They tell match or mismatch,
+//! whose turn it is and who is online only with the colour.
+//! The colour-blind-safe scheme uses colours that stay apart for all kinds of colour blindness
+//! and adds an icon with its own shape to every status. The clickable texts are underlined.
+//! The high-contrast scheme uses the strongest colours on a black or white background,
+//! bold text and the same icons and underlines. Both schemes have a set of colours for light and for dark themes.
+//! The scheme is chosen in the settings and saved in localStorage.
+
+use crate::theme::Theme;
+use crate::{local_storage, GameData};
+use dodrio::bumpalo::{self, Bump};
+use web_sys::console;
+
+///the name of the item in localStorage
+const COLOR_SCHEME_STORAGE_KEY: &str = "mem2_color_scheme";
+
+///what a colour on the screen means
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    ///a match, a right answer, online, a known letter, on
+    Positive,
+    ///one card waits for its pair, lagging, a letter in learning
+    Caution,
+    ///a mismatch, a wrong answer, gone, a letter to repeat, off
+    Negative,
+    ///a letter never found
+    Inactive,
+    ///the player whose turn it is
+    Turn,
+    ///the player who waits for the turn
+    Waiting,
+    ///a text to click
+    Action,
+    ///a text to read
+    Notice,
+}
+
+///the colours and icons of all the status
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorScheme {
+    ///the palette of the theme without icons
+    Standard,
+    ///blue, orange and vermillion with icons
+    ColorBlindSafe,
+    ///bright colours and bold text with icons
+    HighContrast,
+}
+
+impl ColorScheme {
+    ///the scheme from localStorage
+    pub fn load() -> Self {
+        match local_storage()
+            .and_then(|storage| storage.get_item(COLOR_SCHEME_STORAGE_KEY).ok())
+            .and_then(|opt| opt)
+            .as_ref()
+            .map(String::as_str)
+        {
+            Some("color_blind_safe") => ColorScheme::ColorBlindSafe,
+            Some("high_contrast") => ColorScheme::HighContrast,
+            _ => ColorScheme::Standard,
+        }
+    }
+
+    ///the value in localStorage and the css class of the container
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorScheme::Standard => "standard",
+            ColorScheme::ColorBlindSafe => "color_blind_safe",
+            ColorScheme::HighContrast => "high_contrast",
+        }
+    }
+
+    ///the text on the screen
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorScheme::Standard => "colours: standard",
+            ColorScheme::ColorBlindSafe => "colours: colour-blind safe",
+            ColorScheme::HighContrast => "colours: high contrast",
+        }
+    }
+
+    ///standard, colour-blind safe, high contrast and again standard. Save it.
+    pub fn next(&mut self) {
+        *self = match self {
+            ColorScheme::Standard => ColorScheme::ColorBlindSafe,
+            ColorScheme::ColorBlindSafe => ColorScheme::HighContrast,
+            ColorScheme::HighContrast => ColorScheme::Standard,
+        };
+        if let Some(storage) = local_storage() {
+            if storage
+                .set_item(COLOR_SCHEME_STORAGE_KEY, self.as_str())
+                .is_err()
+            {
+                console::log_1(&"error: cannot save the colour scheme to localStorage".into());
+            }
+        }
+    }
+
+    ///the colour of the status on the background of the theme
+    fn color(self, status: Status, theme: &Theme) -> &'static str {
+        let palette = &theme.palette;
+        match (self, theme.is_dark) {
+            (ColorScheme::Standard, _) => match status {
+                Status::Positive | Status::Turn => palette.positive,
+                Status::Caution => palette.caution,
+                Status::Negative | Status::Waiting => palette.negative,
+                Status::Inactive => palette.inactive,
+                Status::Action => palette.action,
+                Status::Notice => palette.notice,
+            },
+            //the Okabe-Ito colours
+            (ColorScheme::ColorBlindSafe, true) => match status {
+                Status::Positive | Status::Turn => "#56B4E9",
+                Status::Caution => "#E69F00",
+                Status::Negative | Status::Waiting => "#D55E00",
+                Status::Inactive => "#999999",
+                Status::Action => "#F0E442",
+                Status::Notice => "#CC79A7",
+            },
+            //the darker Okabe-Ito colours are readable on a light background
+            (ColorScheme::ColorBlindSafe, false) => match status {
+                Status::Positive | Status::Turn => "#0072B2",
+                Status::Caution => "#9A6200",
+                Status::Negative | Status::Waiting => "#C0392B",
+                Status::Inactive => "#666666",
+                Status::Action => "#000000",
+                Status::Notice => "#9E4F7C",
+            },
+            (ColorScheme::HighContrast, true) => match status {
+                Status::Positive | Status::Turn => "#FFFFFF",
+                Status::Caution | Status::Notice => "#FFFF00",
+                Status::Negative => "#FF8080",
+                Status::Inactive | Status::Waiting => "#C0C0C0",
+                Status::Action => "#00FFFF",
+            },
+            (ColorScheme::HighContrast, false) => match status {
+                Status::Positive | Status::Turn => "#000000",
+                Status::Caution | Status::Notice => "#5C3A00",
+                Status::Negative => "#A00000",
+                Status::Inactive | Status::Waiting => "#404040",
+                Status::Action => "#0000C0",
+            },
+        }
+    }
+
+    ///the icon before the text. Every status has its own shape.
+    fn icon(self, status: Status) -> &'static str {
+        if let ColorScheme::Standard = self {
+            return "";
+        }
+        match status {
+            Status::Positive => "✔",
+            Status::Caution => "▲",
+            Status::Negative => "✖",
+            Status::Inactive => "○",
+            Status::Turn => "▶",
+            Status::Waiting => "◇",
+            Status::Action | Status::Notice => "",
+        }
+    }
+
+    ///the inline css of the status
+    pub fn style(self, status: Status, theme: &Theme) -> String {
+        let mut style = format!("color:{};", self.color(status, theme));
+        if self != ColorScheme::Standard && status == Status::Action {
+            style.push_str("text-decoration:underline;");
+        }
+        if self == ColorScheme::HighContrast {
+            style.push_str("font-weight:bold;");
+        }
+        style
+    }
+
+    ///the text with the icon of the status
+    pub fn label_with_icon(self, status: Status, text: &str) -> String {
+        match self.icon(status) {
+            "" => text.to_string(),
+            icon => format!("{} {}", icon, text),
+        }
+    }
+
+    ///the face down cards are dimmed by the theme. In high contrast they are brighter.
+    pub fn face_down_opacity(self, theme: &Theme) -> f64 {
+        if let ColorScheme::HighContrast = self {
+            theme.card_back_opacity.max(0.5)
+        } else {
+            theme.card_back_opacity
+        }
+    }
+}
+
+///the inline css of the status in the chosen scheme and theme
+pub fn css(game_data: &GameData, status: Status) -> String {
+    game_data.color_scheme.style(status, game_data.theme)
+}
+
+///the inline css of the status in the chosen scheme and theme for the vdom
+pub fn style<'a>(game_data: &GameData, status: Status, bump: &'a Bump) -> &'a str {
+    bumpalo::format!(in bump, "{}", css(game_data, status)).into_bump_str()
+}
+
+///the text with the icon of the status in the chosen scheme
+pub fn label<'a>(game_data: &GameData, status: Status, text: &str, bump: &'a Bump) -> &'a str {
+    bumpalo::format!(in bump, "{}", game_data.color_scheme.label_with_icon(status, text))
+        .into_bump_str()
+}
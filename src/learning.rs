@@ -0,0 +1,93 @@
+//! learning.rs - the letter progress of the child on this device.
+//! Every pair found on this device is recorded with the flips of its letter in this game.
+//! The new game chooses more of the letters the child struggles with.
+//! The progress is saved in localStorage and shown per letter after the game.
+
+use crate::indicator::{self, Status};
+use crate::{local_storage, GameData};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use mem2_engine::{morse, LearningProgress, BOX_MAX, CARD_NUMBER_MAX};
+use web_sys::console;
+
+///the name of the item in localStorage
+const LEARNING_STORAGE_KEY: &str = "mem2_learning";
+
+///read from localStorage. If there is nothing or it cannot be parsed, start from the beginning.
+pub fn load() -> LearningProgress {
+    local_storage()
+        .and_then(|storage| storage.get_item(LEARNING_STORAGE_KEY).ok())
+        .and_then(|opt| opt)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+///write to localStorage
+pub fn save(progress: &LearningProgress) {
+    if let Some(storage) = local_storage() {
+        let json = serde_json::to_string(progress).unwrap_or_default();
+        if storage.set_item(LEARNING_STORAGE_KEY, &json).is_err() {
+            console::log_1(&"error: cannot save the letter progress to localStorage".into());
+        }
+    }
+}
+
+///every letter with its mastery. Red must be repeated, green is known, grey was never found.
+///The other colour schemes add icons.
+pub fn div_letter_progress<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    let mut vec_letters = Vec::new();
+    for card_number in 1..=CARD_NUMBER_MAX {
+        let progress = game_data.learning.letter(card_number);
+        let status = if progress.is_new() {
+            Status::Inactive
+        } else if progress.box_level == 0 {
+            Status::Negative
+        } else if progress.box_level < BOX_MAX {
+            Status::Caution
+        } else {
+            Status::Positive
+        };
+        vec_letters.push(
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, status, bump))
+                .attr(
+                    "title",
+                    bumpalo::format!(in bump, "found quickly: {} missed: {}",
+                        progress.found_quickly,
+                        progress.missed)
+                    .into_bump_str(),
+                )
+                .children([text(indicator::label(
+                    game_data,
+                    status,
+                    &format!(
+                        "{} {}%",
+                        morse::card_letter(card_number).unwrap_or(' '),
+                        progress.mastery_percent()
+                    ),
+                    bump,
+                ))])
+                .finish(),
+        );
+    }
+    div(bump)
+        .children([
+            h4(bump)
+                .children([text(
+                    bumpalo::format!(in bump, "Letters after {} games", game_data.learning.game_count)
+                        .into_bump_str(),
+                )])
+                .finish(),
+            div(bump)
+                .attr("class", "grid_container_players")
+                .attr(
+                    "style",
+                    "grid-template-columns: auto auto auto auto auto auto auto;",
+                )
+                .children(vec_letters)
+                .finish(),
+        ])
+        .finish()
+}
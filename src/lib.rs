@@ -55,14 +55,12 @@ use dodrio::bumpalo::{self, Bump};
 use dodrio::{Cached, Node, Render};
 //use futures::{future};
 use futures::Future;
-use js_sys::Reflect;
 use rand::rngs::SmallRng;
-use rand::seq::SliceRandom;
 use rand::FromEntropy;
 use rand::Rng;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{console, WebSocket};
+use web_sys::console;
 //Strum is a set of macros and traits for working with enums and strings easier in Rust.
 use strum_macros::AsRefStr;
 
@@ -73,6 +71,34 @@ use std::rc::Rc;
 //use wasm_bindgen_futures::future_to_promise;
 //use wasm_bindgen_futures::JsFuture;
 //use web_sys::{Request, RequestInit, RequestMode, Response};
+
+mod accessibility;
+mod audio;
+mod error;
+mod high_scores;
+mod indicator;
+mod learning;
+mod lobby;
+mod morse_view;
+mod quiz;
+mod settings;
+mod speech;
+mod theme;
+mod tone;
+mod transport;
+use audio::AudioManager;
+use error::{ErrorOverlay, Mem2Error, Mem2Result};
+use indicator::{ColorScheme, Status};
+use mem2_engine::{
+    new_room_secret, Card, CardAnimation, CardAnimations, CardStatusCardFace, EngineError,
+    GameEvent, GameSettings, GameState, GameView, Heartbeat, HighScoreEntry, HighScoreKey,
+    HighScores, IllegalTransition, LearningProgress, Lobby, MatchRule, PeerPresence, PeerSession,
+    PresenceStatus, Quiz, QuizPrompt, RoomKey, SessionKey, StateAction, WireFormat, WsMessage,
+    CARD_NUMBER_MAX, PRESENCE_INTERVAL_MS,
+};
+use speech::Speech;
+use theme::Theme;
+use transport::{Frame, Transport, TransportKind};
 //endregion
 
 //region: enum, structs, const,...
@@ -80,6 +106,16 @@ use std::rc::Rc;
 const GAME_TITLE: &str = "mem2";
 ///fixed filename for card face down
 const SRC_FOR_CARD_FACE_DOWN: &str = "img/mem_image_00_cardfacedown.png";
+///with auto-flip the cards that don't match are flipped back after 2 seconds
+const AUTO_FLIP_DELAY_MS: f64 = 2_000.0;
+///an invitation without an answer expires after 30 seconds
+const INVITATION_TIMEOUT_MS: f64 = 30_000.0;
+///a sealed message without `Ack` is sent again after 3 seconds
+const RETRANSMIT_MS: f64 = 3_000.0;
+///a closed WebSocket is opened again every 3 seconds
+const RECONNECT_INTERVAL_MS: f64 = 3_000.0;
+///the url fragment parameter with the room secret of the invite link
+const ROOM_FRAGMENT: &str = "room=";
 
 ///Text of game rules.
 ///Multiline string literal just works.
@@ -89,14 +125,17 @@ const SRC_FOR_CARD_FACE_DOWN: &str = "img/mem_image_00_cardfacedown.png";
 const GAME_RULES:& str = "This game is for exactly 2 players. 
 Both players must have the webpage simultaneously opened in their browsers to allow communication.
 To start over just refresh the webpage.
-The first player clicks on 'Ask Player2 to play?' and broadcasts the message over WebSocket.
+The first player clicks on an idle player in the list of players online and sends him the challenge over WebSocket.
 Player2 then sees on the screen 'Click here to Accept play!', clicks it and sends the message back to Player1.
-The game starts with a grid of 8 randomly shuffled card pairs face down - 16 cards in all.
+Player2 can also decline. Player1 can cancel the invitation. Without an answer it expires after 30 seconds.
+The invitation carries the settings: grid size, deck, match rule, time limit and auto-flip. The other player accepts them or counter-proposes his own.
+The game starts with a grid of randomly shuffled card pairs face down - 16 cards in the default 4x4 grid.
 On the screen under the grid are clear signals which player plays and which waits.
 Player1 flips over two cards with two clicks.
 If the cards do not match, the other player clicks on 'Click here to Take your turn !' and both cards are flipped back face down. Then it is his turn and he clicks to flip over his two cards.
 If the cards match, they are left face up permanently and the player receives a point. He continues to play, he opens the next two cards.
-The player with more points wins.";
+The player with more points wins.
+Two players can also play on one device with 'Play on this device'. They pass the device to each other between turns.";
 
 ///game description
 const GAME_DESCRIPTION:& str = "Learning to use Rust Wasm/WebAssembly with Dodrio Virtual Dom and WebSockets communication - second iteration. 
@@ -115,75 +154,28 @@ struct Spelling {
     name: Vec<String>,
 }
 
-///`WsMessage` enum for websocket
-#[derive(Serialize, Deserialize)]
-enum WsMessage {
-    ///connection test
-    ConnectionTest {
-        ///anything
-        test: String,
-    },
-    ///want to play
-    WantToPlay {
-        ///ws client instance unique id. To not listen the echo to yourself.
-        ws_client_instance: usize,
-    },
-    /// accept play
-    AcceptPlay {
-        ///ws client instance unique id. To not listen the echo to yourself.
-        ws_client_instance: usize,
-        ///act is the action to take on the receiver
-        card_grid_data: String,
-    },
-    ///player click
-    PlayerClick {
-        ///ws client instance unique id. To not listen the echo to yourself.
-        ws_client_instance: usize,
-        ///card_index
-        card_index: usize,
-        ///count click inside one turn
-        count_click_inside_one_turn: usize,
-    },
-    ///player change
-    PlayerChange {
-        ///ws client instance unique id. To not listen the echo to yourself.
-        ws_client_instance: usize,
-    },
-}
-
-///the game can be in various states and that differentiate the UI and actions
+///the game can be played in different modes. The high scores are separate for every mode.
 #[derive(AsRefStr)]
-enum GameState {
-    ///the start of the game
-    Start,
-    ///Player1 Asking WantToPlay
-    Asking,
-    ///Player2 is asked WantToPlay
-    Asked,
-    ///play (the turn is in cardgrid.player_turn)
-    Play,
-}
-
-///the 3 possible states of one card
-#[derive(Serialize, Deserialize)]
-enum CardStatusCardFace {
-    ///card face down
-    Down,
-    ///card face Up Temporary
-    UpTemporary,
-    ///card face up Permanently
-    UpPermanently,
+enum GameMode {
+    ///two players on two machines communicating over WebSocket
+    Multiplayer,
+    ///two players take turns on the same device. No WebSocket.
+    HotSeat,
+    ///one player alone on this device beats his own records. No turns and no WebSocket.
+    Solo,
+    ///the server owns the cards and the rules. The clients send only what card to flip.
+    ServerAuthoritative,
 }
 
-///all the data for one card
-#[derive(Serialize, Deserialize)]
-struct Card {
-    ///card status
-    status: CardStatusCardFace,
-    ///field for src attribute for HTML element imagea and filename of card image
-    card_number_and_img_src: usize,
-    ///field for id attribute for HTML element image contains the card index
-    card_index_and_id: usize,
+///how the last invitation ended without a game. It is shown on the start screen.
+#[derive(Clone, Copy)]
+enum InvitationOutcome {
+    ///the asked player declined
+    Declined,
+    ///the player that asked cancelled
+    Cancelled,
+    ///nobody answered in time
+    Expired,
 }
 
 ///Render Component: player score (cacheable?)
@@ -203,6 +195,8 @@ struct RootRenderingComponent {
     players_and_scores: PlayersAndScores,
     ///subComponent: the static parts can be cached. I am not sure if a field in this struct is the best place to put it.
     cached_rules_and_description: Cached<RulesAndDescription>,
+    ///subComponent: recoverable errors
+    error_overlay: ErrorOverlay,
 }
 ///game data
 struct GameData {
@@ -220,16 +214,44 @@ struct GameData {
     card_index_of_second_click: usize,
     ///counts only clicks that flip the card. The third click is not counted.
     count_all_clicks: usize,
-    ///web socket. used it to send message onclick.
-    ws: WebSocket,
+    ///the clicks of player1 and player2 that flip the card. In a game on two machines only mine are counted.
+    vec_player_clicks: [usize; 2],
+    ///transport (WebSocket or other) used to send message onclick.
+    transport: Rc<dyn Transport>,
     ///my ws client instance unique id. To not listen the echo to yourself.
     my_ws_client_instance: usize,
     ///other ws client instance unique id. To listen only to one accepted other player.
     other_ws_client_instance: usize,
     ///game state: Start,Asking,Asked,Player1,Player2
     game_state: GameState,
-    ///content folder name
-    content_folder_name: String,
+    ///the agreed settings of this game. The deck is the content folder name.
+    settings: GameSettings,
+    ///my settings for the next invitation or hot seat game
+    my_settings: GameSettings,
+    ///the settings proposed in the invitation, by me or by the other player
+    settings_proposal: GameSettings,
+    ///the wire format of the sent messages. Json until the handshake agrees on binary.
+    wire_format: WireFormat,
+    ///the wire format I propose in the handshake
+    my_wire_format: WireFormat,
+    ///the wire format the other player proposed in the handshake
+    wire_format_proposal: WireFormat,
+    ///the room secret from the invite link. It never travels over the wire.
+    room_secret: String,
+    ///the lobby and the invitation are sealed with the key of the room
+    room_key: RoomKey,
+    ///the page address with the room secret. The other player opens it to play in the same room.
+    invite_link: String,
+    ///my random number for the session key of this invitation
+    my_nonce: u64,
+    ///the random number of the other player for the session key
+    nonce_proposal: u64,
+    ///the game messages with the other player are sealed with the key of this game
+    peer_session: Option<PeerSession>,
+    ///the transport was closed and is not open again yet
+    is_reconnecting: bool,
+    ///time of the last reconnect from js Date.now()
+    last_reconnect_attempt: f64,
     ///What player am I
     this_machine_player_number: usize,
     ///whose turn is now:  player 1 or 2
@@ -238,6 +260,52 @@ struct GameData {
     player1_points: usize,
     ///player2 points
     player2_points: usize,
+    ///game mode is part of the high score key
+    game_mode: GameMode,
+    ///time of the start of play in milliseconds from js Date.now()
+    game_start_time: f64,
+    ///duration of the finished game in milliseconds
+    game_duration_ms: f64,
+    ///the invitation expires at this time from js Date.now(). None if there is no invitation.
+    invitation_deadline: Option<f64>,
+    ///how the last invitation ended
+    invitation_outcome: Option<InvitationOutcome>,
+    ///time of the second click in the turn from js Date.now(), for auto-flip
+    second_click_time: f64,
+    ///my name in the lobby
+    player_name: String,
+    ///the other players online
+    lobby: Lobby,
+    ///time of my last `Presence` message from js Date.now()
+    last_presence_sent: f64,
+    ///ping/pong with the other player in the game
+    heartbeat: Heartbeat,
+    ///high scores from localStorage
+    high_scores: HighScores,
+    ///the cached sounds, mute and volume
+    audio: AudioManager,
+    ///the spoken spelling word
+    speech: Speech,
+    ///what the child knows about every letter, from localStorage
+    learning: LearningProgress,
+    ///how many times the cards of every letter were flipped in this game. Index is the card number.
+    vec_letter_flips: Vec<usize>,
+    ///the quiz mini-game
+    quiz: Quiz,
+    ///the flip, match and mismatch animations of the cards
+    card_animations: CardAnimations,
+    ///the ranks of the results of the last finished game in the high scores table, if they got in the table
+    vec_high_score_ranks: Vec<usize>,
+    ///the card back, the colours and the fonts, from localStorage
+    theme: &'static Theme,
+    ///the colours and icons of the status, from localStorage
+    color_scheme: ColorScheme,
+    ///the card in the tab order of the grid. The arrow keys move it.
+    focused_card_index: usize,
+    ///the last text for the screen reader in the live region
+    announcement: String,
+    ///recoverable errors shown in the error overlay until dismissed
+    vec_errors: Vec<Mem2Error>,
 }
 //endregion
 
@@ -249,28 +317,59 @@ pub fn run() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
 
     // Get the document's container to render the virtual dom component.
-    let window = web_sys::window().expect("error: web_sys::window");
+    //without them the game cannot start. The error is returned to javascript.
+    let window = web_sys::window().ok_or(Mem2Error::NoWindow)?;
 
-    let document = window.document().expect("error: window.document");
+    let document = window.document().ok_or(Mem2Error::NoDocument)?;
     let div_for_virtual_dom = document
         .get_element_by_id("div_for_virtual_dom")
-        .expect("No #div_for_virtual_dom");
+        .ok_or_else(|| Mem2Error::ElementNotFound("div_for_virtual_dom".to_string()))?;
 
     let mut rng = SmallRng::from_entropy();
     //gen_range is lower inclusive, upper exclusive 26 + 1
     let my_ws_client_instance: usize = rng.gen_range(1, 9999);
 
-    //find out URL
-    let location_href = window.location().href().expect("href not known");
+    //find out URL. The WebSocket is on the same host and port.
+    let location = window.location();
+    let ws_url = transport::web_socket_url(&location.protocol()?, &location.host()?);
+    //the transport is chosen with the url query ?transport=
+    let location_search = location.search().unwrap_or_default();
+    //the fragment is never sent to the server
+    let location_hash = location.hash().unwrap_or_default();
+    //without a room in the link this page makes a new room. The link with it is the invitation.
+    let mut room_secret = room_secret_from_location_hash(&location_hash);
+    if room_secret.is_empty() {
+        room_secret = new_room_secret(&mut rand::thread_rng());
+        let hash = location_hash.trim_start_matches('#');
+        let separator = if hash.is_empty() { "" } else { "&" };
+        location.set_hash(&format!(
+            "{}{}{}{}",
+            hash, separator, ROOM_FRAGMENT, room_secret
+        ))?;
+    }
+    let invite_link = location.href()?;
 
-    //websocket connection
-    let ws = setup_ws_connection(location_href.as_str());
-    //I don't know why is needed to clone the websocket connection
-    let ws_c = ws.clone();
+    //websocket or other transport connection
+    let transport = setup_transport_connection(
+        &TransportKind::from_location_search(&location_search),
+        &ws_url,
+    )?;
 
     // Construct a new `RootRenderingComponent`.
-    //I added ws_c so that I can send messages on websocket
-    let game_data = GameData::new(ws_c, my_ws_client_instance);
+    //I added the transport so that I can send messages on websocket
+    //the disconnect timeout is configurable with the url query ?disconnect_timeout=
+    //and the json fallback with ?encoding=json
+    //the room secret of the invite link is the fragment #room=
+    let game_data = GameData::new(
+        Rc::clone(&transport),
+        my_ws_client_instance,
+        Heartbeat::from_location_search(&location_search),
+        transport::wire_format_from_location_search(&location_search),
+        room_secret.clone(),
+        invite_link,
+    );
+    //the page colours and fonts of the saved theme
+    theme::apply(game_data.theme, game_data.color_scheme);
     //TODO: I need to make a Rc<RefCell<GameData>>
     let rc = Rc::new(RefCell::new(game_data));
     let root_rendering_component = RootRenderingComponent::new(rc);
@@ -279,7 +378,10 @@ pub fn run() -> Result<(), JsValue> {
     let vdom = dodrio::Vdom::new(&div_for_virtual_dom, root_rendering_component);
 
     //websocket on receive message callback
-    setup_ws_msg_recv(&ws, &vdom);
+    setup_transport_msg_recv(&*transport, RoomKey::derive(&room_secret), &vdom);
+
+    //every second check the timeouts
+    setup_clock_tick(&window, &vdom)?;
 
     // Run the component forever. Forget to drop the memory.
     vdom.forget();
@@ -288,6 +390,29 @@ pub fn run() -> Result<(), JsValue> {
 }
 //endregion
 
+///the room secret is the value of the url fragment `#room=`. Empty if the link has no room yet.
+///The browser does not send the fragment to the server, so the secret stays on this machine.
+fn room_secret_from_location_hash(hash: &str) -> String {
+    hash.trim_start_matches('#')
+        .split('&')
+        .find(|pair| pair.starts_with(ROOM_FRAGMENT))
+        .map(|pair| &pair[ROOM_FRAGMENT.len()..])
+        .and_then(|value| js_sys::decode_uri_component(value).ok())
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+///a new random number for the session key
+fn new_nonce() -> u64 {
+    SmallRng::from_entropy().gen()
+}
+
+///pairs of the letters chosen by the learning progress, shuffled
+fn new_learning_cards(learning: &LearningProgress, card_pairs: usize) -> Vec<Card> {
+    let vec_card_numbers = learning.choose_letters(card_pairs, &mut SmallRng::from_entropy());
+    mem2_engine::new_cards_from_letters(&vec_card_numbers)
+}
+
 ///change the newline lines ending into <br> node
 fn text_with_br_newline<'a>(txt: &'a str, bump: &'a Bump) -> Vec<Node<'a>> {
     let mut vec_text_node = Vec::new();
@@ -301,55 +426,23 @@ fn text_with_br_newline<'a>(txt: &'a str, bump: &'a Bump) -> Vec<Node<'a>> {
 
 impl GameData {
     ///constructor of game data
-    pub fn new(ws: WebSocket, my_ws_client_instance: usize) -> Self {
-        //region: find 8 distinct random numbers between 1 and 26 for the alphabet cards
-        //vec_of_random_numbers is 0 based
-        let mut vec_of_random_numbers = Vec::new();
-        let mut rng = SmallRng::from_entropy();
-        let mut i = 0;
-        while i < 8 {
-            //gen_range is lower inclusive, upper exclusive 26 + 1
-            let num: usize = rng.gen_range(1, 27);
-            if vec_of_random_numbers.contains(&num) {
-                //do nothing if the random number is repeated
-                //debug!("random duplicate {} in {:?}", num, vec_of_random_numbers);
-            } else {
-                //push a pair of the same number
-                vec_of_random_numbers.push(num);
-                vec_of_random_numbers.push(num);
-                i += 1;
-            }
-        }
-        //endregion
-
-        //region: shuffle the numbers
-        let vrndslice = vec_of_random_numbers.as_mut_slice();
-        vrndslice.shuffle(&mut rng);
-        //endregion
-
-        //region: create Cards from random numbers
-        let mut vec_cards = Vec::new();
-
-        //Index 0 is special and reserved for FaceDown. Cards start with base 1
-        let new_card = Card {
-            status: CardStatusCardFace::Down,
-            card_number_and_img_src: 0,
-            card_index_and_id: 0,
-        };
-        vec_cards.push(new_card);
-
-        //create the 16 card and push to the vector
-        for (index, random_number) in vec_of_random_numbers.iter().enumerate() {
-            let new_card = Card {
-                status: CardStatusCardFace::Down,
-                //dereference random number from iterator
-                card_number_and_img_src: *random_number,
-                //card base index will be 1. 0 is reserved for FaceDown.
-                card_index_and_id: index.checked_add(1).expect("usize overflow"),
-            };
-            vec_cards.push(new_card);
+    pub fn new(
+        transport: Rc<dyn Transport>,
+        my_ws_client_instance: usize,
+        heartbeat: Heartbeat,
+        my_wire_format: WireFormat,
+        room_secret: String,
+        invite_link: String,
+    ) -> Self {
+        //pairs of the alphabet cards, shuffled. The letters the child struggles with come more often.
+        let settings = GameSettings::default();
+        let learning = learning::load();
+        let vec_cards = new_learning_cards(&learning, settings.card_pairs());
+        //the sounds of the default deck are downloaded while the players find each other
+        let mut audio = AudioManager::load();
+        if let Err(err) = audio.preload(&settings.deck) {
+            console::log_1(&format!("sounds not preloaded: {}", err).into());
         }
-        //endregion
 
         //return from constructor
         GameData {
@@ -358,15 +451,705 @@ impl GameData {
             card_index_of_first_click: 0,
             card_index_of_second_click: 0,
             count_all_clicks: 0,
-            ws,
+            vec_player_clicks: [0, 0],
+            transport,
             my_ws_client_instance,
             other_ws_client_instance: 0, //zero means not accepted yet
             game_state: GameState::Start,
-            content_folder_name: "content".to_string(),
+            my_settings: settings.clone(),
+            settings_proposal: settings.clone(),
+            settings,
+            wire_format: WireFormat::Json,
+            my_wire_format,
+            wire_format_proposal: WireFormat::Json,
+            room_key: RoomKey::derive(&room_secret),
+            room_secret,
+            invite_link,
+            my_nonce: 0,
+            nonce_proposal: 0,
+            peer_session: None,
+            is_reconnecting: false,
+            last_reconnect_attempt: 0.0,
             player1_points: 0,
             player2_points: 0,
             this_machine_player_number: 0, //unknown until WantToPlay+Accept
             player_turn: 0,
+            game_mode: GameMode::Multiplayer,
+            game_start_time: 0.0,
+            game_duration_ms: 0.0,
+            invitation_deadline: None,
+            invitation_outcome: None,
+            second_click_time: 0.0,
+            player_name: lobby::load_player_name(my_ws_client_instance),
+            lobby: Lobby::default(),
+            last_presence_sent: 0.0,
+            heartbeat,
+            high_scores: high_scores::load(),
+            vec_high_score_ranks: Vec::new(),
+            audio,
+            speech: Speech::load(),
+            learning,
+            vec_letter_flips: vec![0; CARD_NUMBER_MAX + 1],
+            quiz: Quiz::default(),
+            card_animations: CardAnimations::default(),
+            theme: theme::load(),
+            color_scheme: ColorScheme::load(),
+            focused_card_index: 1,
+            announcement: String::new(),
+            vec_errors: Vec::new(),
+        }
+    }
+    ///only the multiplayer mode sends messages over WebSocket
+    fn is_multiplayer(&self) -> bool {
+        if let GameMode::Multiplayer = self.game_mode {
+            true
+        } else {
+            false
+        }
+    }
+    ///the server holds the cards and sends only the view
+    fn is_server_authoritative(&self) -> bool {
+        if let GameMode::ServerAuthoritative = self.game_mode {
+            true
+        } else {
+            false
+        }
+    }
+    ///one player alone: the turn never changes
+    fn is_solo(&self) -> bool {
+        if let GameMode::Solo = self.game_mode {
+            true
+        } else {
+            false
+        }
+    }
+    ///two players on the same device pass it between the turns
+    fn is_hot_seat(&self) -> bool {
+        if let GameMode::HotSeat = self.game_mode {
+            true
+        } else {
+            false
+        }
+    }
+    ///the only way to change the game state. The state machine rejects the events not allowed in this state.
+    fn fire(&mut self, event: GameEvent) -> Mem2Result<()> {
+        let transition = self.game_state.handle(event)?;
+        self.game_state = transition.to;
+        for action in transition.vec_actions {
+            self.run_state_action(action);
+        }
+        //the lobby of the other players shows the new status
+        self.announce_presence();
+        Ok(())
+    }
+    ///only the players on the start screen can be challenged
+    fn presence_status(&self) -> PresenceStatus {
+        match self.game_state {
+            GameState::Start => PresenceStatus::Idle,
+            _ => PresenceStatus::InGame,
+        }
+    }
+    ///send my name and status to the lobby of the other players.
+    ///It is repeated every few seconds, so the errors are only logged.
+    fn announce_presence(&mut self) {
+        self.last_presence_sent = js_sys::Date::now();
+        let msg = WsMessage::Presence {
+            ws_client_instance: self.my_ws_client_instance,
+            player_name: self.player_name.clone(),
+            status: self.presence_status(),
+        };
+        if let Err(err) = self.send_ws_message(&msg) {
+            console::log_1(&format!("presence not sent: {}", err).into());
+        }
+    }
+    ///called every second. Returns true if the lobby changed.
+    fn check_presence(&mut self) -> bool {
+        let now = js_sys::Date::now();
+        if now - self.last_presence_sent >= PRESENCE_INTERVAL_MS {
+            self.announce_presence();
+        }
+        let count_players = self.lobby.vec_players.len();
+        self.lobby.prune(now);
+        count_players != self.lobby.vec_players.len()
+    }
+    ///only a game with the other player on another machine has a heartbeat
+    fn has_heartbeat(&self) -> bool {
+        match (self.game_state, &self.game_mode) {
+            (GameState::Play, GameMode::HotSeat) | (GameState::Play, GameMode::Solo) => false,
+            (GameState::Play, _) => self.other_ws_client_instance != 0,
+            _ => false,
+        }
+    }
+    ///called every second. Sends the `Ping` when it is due. Returns true if the indicator must be rendered.
+    fn check_heartbeat(&mut self) -> bool {
+        if !self.has_heartbeat() {
+            return false;
+        }
+        let now = js_sys::Date::now();
+        if self.heartbeat.is_ping_due(now) {
+            self.heartbeat.last_ping_sent = now;
+            let msg = WsMessage::Ping {
+                ws_client_instance: self.my_ws_client_instance,
+                other_ws_client_instance: self.other_ws_client_instance,
+                sent_at: now,
+            };
+            //it is repeated every few seconds, so the errors are only logged
+            if let Err(err) = self.send_heartbeat(msg) {
+                console::log_1(&format!("ping not sent: {}", err).into());
+            }
+        }
+        true
+    }
+    ///online, lagging or gone. None if there is no other player on another machine.
+    fn peer_presence(&self) -> Option<PeerPresence> {
+        if self.has_heartbeat() {
+            Some(self.heartbeat.presence(js_sys::Date::now()))
+        } else {
+            None
+        }
+    }
+    ///the game is built from the agreed settings: new cards for the grid size
+    fn apply_settings(&mut self, settings: GameSettings) {
+        self.vec_cards = new_learning_cards(&self.learning, settings.card_pairs());
+        self.settings = settings;
+        self.preload_sounds();
+    }
+    ///the sounds of the agreed deck are downloaded before the first flip
+    fn preload_sounds(&mut self) {
+        if let Err(err) = self.audio.preload(&self.settings.deck) {
+            self.show_error(err);
+        }
+    }
+    ///milliseconds until the time limit. None if the game has no time limit.
+    fn time_left_ms(&self) -> Option<f64> {
+        if self.settings.time_limit_s == 0 {
+            None
+        } else {
+            let time_limit_ms = f64::from(self.settings.time_limit_s) * 1000.0;
+            Some((time_limit_ms - (js_sys::Date::now() - self.game_start_time)).max(0.0))
+        }
+    }
+    ///the countdown is shown only in play
+    fn time_left_text(&self) -> String {
+        match (self.game_state, self.time_left_ms()) {
+            (GameState::Play, Some(time_left_ms)) => {
+                format!(" Time left: {} s", (time_left_ms / 1000.0).ceil())
+            }
+            _ => String::new(),
+        }
+    }
+    ///called every second. Returns true if the countdown must be rendered.
+    fn check_time_limit(&mut self) -> bool {
+        match (self.game_state, self.time_left_ms()) {
+            (GameState::Play, Some(time_left_ms)) => {
+                if time_left_ms <= 0.0 {
+                    if let Err(err) = self.fire(GameEvent::TimeIsUp) {
+                        self.show_error(err);
+                    }
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+    ///with auto-flip the player that takes the turn does it automatically after two cards that don't match
+    fn is_auto_flip_due(&self) -> bool {
+        self.settings.auto_flip
+            && (self.is_multiplayer() || self.is_server_authoritative())
+            && self.game_state == GameState::Play
+            && self.count_click_inside_one_turn == 2
+            && self.this_machine_player_number != self.player_turn
+            && js_sys::Date::now() - self.second_click_time >= AUTO_FLIP_DELAY_MS
+    }
+    ///received messages can be late or meant for somebody else. The events not allowed now are only logged.
+    fn accepts_received(&self, event: GameEvent) -> bool {
+        let accepts = self.game_state.accepts(event);
+        if !accepts {
+            console::log_1(
+                &format!(
+                    "ignored message: {}",
+                    IllegalTransition {
+                        state: self.game_state,
+                        event
+                    }
+                )
+                .into(),
+            );
+        }
+        accepts
+    }
+    ///exit and entry actions of the game states
+    fn run_state_action(&mut self, action: StateAction) {
+        match action {
+            //the start of play is the start of the time measurement for the high scores
+            StateAction::StartClock => {
+                self.player_turn = 1;
+                self.game_start_time = js_sys::Date::now();
+                self.heartbeat.reset(self.game_start_time);
+                self.vec_letter_flips = vec![0; CARD_NUMBER_MAX + 1];
+                self.card_animations.clear();
+            }
+            StateAction::StopClock => {
+                self.game_duration_ms = js_sys::Date::now() - self.game_start_time;
+            }
+            StateAction::FinishGame => {
+                self.learning.finish_game();
+                learning::save(&self.learning);
+            }
+            StateAction::RecordHighScore => self.record_high_score(),
+            StateAction::StartInvitationTimer => {
+                self.invitation_deadline = Some(js_sys::Date::now() + INVITATION_TIMEOUT_MS);
+            }
+            StateAction::StopInvitationTimer => self.invitation_deadline = None,
+            StateAction::NewQuiz => {
+                self.quiz = Quiz::default();
+                self.next_quiz_question();
+            }
+        }
+    }
+    ///whole seconds until the invitation expires
+    fn invitation_seconds_left(&self) -> f64 {
+        self.invitation_deadline.map_or(0.0, |deadline| {
+            ((deadline - js_sys::Date::now()) / 1000.0).ceil().max(0.0)
+        })
+    }
+    ///the invitation ends without a game and both sides return to the start screen
+    fn end_invitation(&mut self, event: GameEvent, outcome: Option<InvitationOutcome>) {
+        //the player that asked tells the other side and the server
+        let send_cancel = match (self.game_state, event) {
+            (GameState::Asking, GameEvent::CancelInvite)
+            | (GameState::Asking, GameEvent::InvitationExpired) => true,
+            _ => false,
+        };
+        if let Err(err) = self.fire(event) {
+            self.show_error(err);
+            return;
+        }
+        if send_cancel {
+            let msg = WsMessage::CancelInvite {
+                ws_client_instance: self.my_ws_client_instance,
+            };
+            if let Err(err) = self.send_ws_message(&msg) {
+                self.show_error(err);
+            }
+        }
+        self.other_ws_client_instance = 0;
+        self.invitation_outcome = outcome;
+    }
+    ///called every second. Returns true if the countdown must be rendered.
+    fn check_invitation_timeout(&mut self) -> bool {
+        match self.invitation_deadline {
+            None => false,
+            Some(deadline) => {
+                if js_sys::Date::now() >= deadline {
+                    self.end_invitation(
+                        GameEvent::InvitationExpired,
+                        Some(InvitationOutcome::Expired),
+                    );
+                }
+                true
+            }
+        }
+    }
+    ///the game is over when all the cards are face up permanently. Card 0 is only a placeholder.
+    fn is_game_over(&self) -> bool {
+        self.vec_cards.iter().skip(1).all(|card| {
+            if let CardStatusCardFace::UpPermanently = card.status {
+                true
+            } else {
+                false
+            }
+        })
+    }
+    ///the player number of the other player
+    fn other_player_number(&self) -> usize {
+        if self.this_machine_player_number == 1 {
+            2
+        } else {
+            1
+        }
+    }
+    ///the other player is not trusted. His click must be valid in the current state.
+    fn validate_player_click(
+        &self,
+        card_index: usize,
+        count_click_inside_one_turn: usize,
+    ) -> Result<(), EngineError> {
+        match self.game_state {
+            GameState::Play => {}
+            _ => return Err(EngineError::GameNotInPlay),
+        }
+        if count_click_inside_one_turn != self.count_click_inside_one_turn + 1 {
+            return Err(EngineError::WrongClickCount);
+        }
+        mem2_engine::check_flip(
+            &self.vec_cards,
+            self.player_turn,
+            self.count_click_inside_one_turn,
+            self.other_player_number(),
+            card_index,
+        )
+    }
+    ///the other player can take the turn only after my two cards don't match
+    fn validate_player_change(&self) -> Result<(), EngineError> {
+        match self.game_state {
+            GameState::Play => {}
+            _ => return Err(EngineError::GameNotInPlay),
+        }
+        mem2_engine::check_take_turn(
+            self.player_turn,
+            self.count_click_inside_one_turn,
+            self.other_player_number(),
+        )
+    }
+    ///serialize and send the message over the transport
+    fn send_ws_message(&self, ws_message: &WsMessage) -> Mem2Result<()> {
+        //the lobby and the invitation are sealed with the room key
+        let room_sealed;
+        let ws_message = if ws_message.needs_room_seal() {
+            room_sealed = self.room_key.seal(ws_message.clone())?;
+            &room_sealed
+        } else {
+            ws_message
+        };
+        match self.wire_format {
+            WireFormat::Json => self.transport.send(&serde_json::to_string(ws_message)?),
+            WireFormat::Binary => self.transport.send_binary(&ws_message.to_binary()?),
+        }
+    }
+    ///the game messages to the other player are sealed, if the game has a session key.
+    ///The sealed messages are sent again until the `Ack`.
+    fn send_game_message(&mut self, ws_message: WsMessage) -> Mem2Result<()> {
+        let ws_message = match self.peer_session.as_mut() {
+            Some(peer_session) => peer_session.seal(ws_message, js_sys::Date::now())?,
+            None => ws_message,
+        };
+        self.send_ws_message(&ws_message)
+    }
+    ///the `Ping` and `Pong` are sealed, if the game has a session key, but never sent again.
+    ///The next heartbeat comes soon and an old round-trip would be wrong.
+    fn send_heartbeat(&mut self, ws_message: WsMessage) -> Mem2Result<()> {
+        let ws_message = match self.peer_session.as_mut() {
+            Some(peer_session) => peer_session.seal_heartbeat(ws_message)?,
+            None => ws_message,
+        };
+        self.send_ws_message(&ws_message)
+    }
+    ///acknowledge the sealed messages received in order. A lost `Ack` is repeated with the next one.
+    fn send_ack(&self) {
+        let result = self
+            .peer_session
+            .as_ref()
+            .map(|peer_session| peer_session.ack().map_err(Mem2Error::from))
+            .map(|ack| ack.and_then(|ack| self.send_ws_message(&ack)));
+        if let Some(Err(err)) = result {
+            console::log_1(&format!("ack not sent: {}", err).into());
+        }
+    }
+    ///called every second. Opens again a closed transport.
+    ///After the reconnect all the sealed messages without `Ack` are sent again, else only the old ones.
+    fn check_connection(&mut self) {
+        let now = js_sys::Date::now();
+        if !self.transport.is_open() {
+            self.is_reconnecting = true;
+            if now - self.last_reconnect_attempt >= RECONNECT_INTERVAL_MS {
+                self.last_reconnect_attempt = now;
+                if let Err(err) = self.transport.reconnect() {
+                    console::log_1(&format!("reconnect failed: {}", err).into());
+                }
+            }
+            return;
+        }
+        let retransmit_ms = if self.is_reconnecting {
+            0.0
+        } else {
+            RETRANSMIT_MS
+        };
+        let vec_sealed = self
+            .peer_session
+            .as_mut()
+            .map_or_else(Vec::new, |peer_session| {
+                peer_session.retransmit(now, retransmit_ms)
+            });
+        for sealed in &vec_sealed {
+            if let Err(err) = self.send_ws_message(sealed) {
+                console::log_1(&format!("retransmit failed: {}", err).into());
+            }
+        }
+        if self.is_reconnecting {
+            self.is_reconnecting = false;
+            //the lobby of the other players forgot me
+            self.announce_presence();
+        }
+    }
+    ///with a game session the game messages must be sealed, else anybody on the relay could forge them
+    fn accepts_sealed(&self, is_sealed: bool) -> bool {
+        if is_sealed || self.peer_session.is_none() {
+            true
+        } else {
+            console::log_1(&"dropped message: it is not sealed".into());
+            false
+        }
+    }
+    ///the error is written in the console and shown in the error overlay. The same error only once.
+    fn show_error(&mut self, err: Mem2Error) {
+        console::log_1(&format!("error: {}", err).into());
+        if !self.vec_errors.contains(&err) {
+            self.vec_errors.push(err);
+        }
+    }
+    ///the screen reader reads the text in the live region.
+    ///The same text again gets an invisible space, so it is read again.
+    fn announce(&mut self, announcement: String) {
+        self.announcement = if announcement == self.announcement {
+            format!("{}\u{a0}", announcement)
+        } else {
+            announcement
+        };
+    }
+    ///the rejected message is logged and the sender receives the reason
+    fn reject_message(&mut self, other_ws_client_instance: usize, reason: &EngineError) {
+        console::log_1(
+            &format!(
+                "rejected message from {}: {}",
+                other_ws_client_instance, reason
+            )
+            .into(),
+        );
+        if let Err(err) = self.send_ws_message(&WsMessage::ProtocolError {
+            ws_client_instance: self.my_ws_client_instance,
+            other_ws_client_instance,
+            reason: reason.to_string(),
+        }) {
+            self.show_error(err);
+        }
+    }
+    ///audio play the morse sound of the card
+    fn play_card_sound(&mut self, card_index: usize) -> Mem2Result<()> {
+        let card_number = self
+            .vec_cards
+            .get(card_index)
+            .ok_or(Mem2Error::CardIndex(card_index))?
+            .card_number_and_img_src;
+        self.audio.play(&self.settings.deck, card_number)?;
+        self.speak_card(card_number);
+        Ok(())
+    }
+    ///speak the spelling word of the card after its Morse sound.
+    ///Until the spelling is loaded there is nothing to speak.
+    fn speak_card(&self, card_number: usize) {
+        if self.audio.is_muted {
+            return;
+        }
+        let word = get_spelling()
+            .ok()
+            .and_then(|spelling| spelling.name.get(card_number).cloned());
+        let text = word.and_then(|word| {
+            mem2_engine::morse::card_letter(card_number)
+                .and_then(|letter| self.speech.utterance_text(letter, &word))
+        });
+        if let Some(text) = text {
+            self.speech.speak_after(
+                text,
+                self.audio.sound_duration_ms(card_number),
+                self.audio.volume,
+            );
+        }
+    }
+    ///the player hears the new speech settings on the first card
+    fn speak_sample(&self) {
+        let word = get_spelling()
+            .ok()
+            .and_then(|spelling| spelling.name.get(1).cloned())
+            .unwrap_or_else(|| "alpha".to_string());
+        if let Some(text) = self.speech.utterance_text('A', &word) {
+            self.speech.speak_after(text, 0.0, self.audio.volume);
+        }
+    }
+    ///the next quiz question. The Morse question plays its sound at once.
+    fn next_quiz_question(&mut self) {
+        self.quiz
+            .new_question(&self.learning, &mut SmallRng::from_entropy());
+        let is_morse = self
+            .quiz
+            .question
+            .as_ref()
+            .map_or(false, |question| question.prompt == QuizPrompt::Morse);
+        if is_morse {
+            if let Err(err) = self.play_quiz_sound() {
+                self.show_error(err);
+            }
+        }
+    }
+    ///the Morse code of the quiz question
+    fn play_quiz_sound(&mut self) -> Mem2Result<()> {
+        let card_number = self
+            .quiz
+            .question
+            .as_ref()
+            .map_or(0, |question| question.card_number);
+        self.audio.play(&self.settings.deck, card_number)
+    }
+    ///every flip shows the child where a letter is
+    fn count_letter_flip(&mut self, card_index: usize) {
+        let card_number = self
+            .vec_cards
+            .get(card_index)
+            .map_or(0, |card| card.card_number_and_img_src);
+        if let Some(flips) = self.vec_letter_flips.get_mut(card_number) {
+            *flips += 1;
+        }
+    }
+    ///the player on this device found the pair. The flips of its letter tell how well he knew it.
+    fn record_pair_found(&mut self, card_number: usize) {
+        let flips = self.vec_letter_flips.get(card_number).copied().unwrap_or(0);
+        self.learning.record_pair(card_number, flips);
+        learning::save(&self.learning);
+    }
+    ///server-authoritative mode: the server sends what the players can see.
+    ///The face down cards have the card number 0.
+    fn apply_game_view(&mut self, game_view: GameView) {
+        //the card that was face down and now is face up was just flipped
+        let vec_flipped: Vec<usize> = game_view
+            .vec_cards
+            .iter()
+            .zip(self.vec_cards.iter())
+            .filter(|(new, old)| new.status != old.status && old.status == CardStatusCardFace::Down)
+            .map(|(new, _old)| new.card_index_and_id)
+            .collect();
+        //count only my clicks like in the other modes
+        let is_my_turn = self.player_turn == self.this_machine_player_number;
+        if is_my_turn {
+            for _ in &vec_flipped {
+                self.count_click();
+            }
+        }
+        //the card numbers of the new pairs. Both cards of the pair have the same number.
+        let mut vec_pairs: Vec<usize> = Vec::new();
+        //the animations like in the other modes: the pair celebrates together after the flip
+        let mut vec_matched: Vec<usize> = Vec::new();
+        //the view hides the faces of the cards face down, so the old faces are shown until the flip
+        let mut vec_faces_down: Vec<(usize, usize)> = Vec::new();
+        let now = js_sys::Date::now();
+        for (new, old) in game_view.vec_cards.iter().zip(self.vec_cards.iter()) {
+            if old.status == CardStatusCardFace::Down && new.status != CardStatusCardFace::Down {
+                self.card_animations
+                    .schedule(new.card_index_and_id, CardAnimation::FlipUp, now);
+            } else if old.status != CardStatusCardFace::Down
+                && new.status == CardStatusCardFace::Down
+            {
+                vec_faces_down.push((new.card_index_and_id, old.card_number_and_img_src));
+            }
+            if new.status == CardStatusCardFace::UpPermanently
+                && old.status != CardStatusCardFace::UpPermanently
+            {
+                vec_matched.push(new.card_index_and_id);
+                if !vec_pairs.contains(&new.card_number_and_img_src) {
+                    vec_pairs.push(new.card_number_and_img_src);
+                }
+            }
+        }
+        self.card_animations
+            .schedule_together(&vec_matched, CardAnimation::Match, now);
+        self.card_animations
+            .schedule_flip_down(&vec_faces_down, now);
+        let is_turn_changed = self.player_turn != game_view.player_turn;
+        //the auto-flip waits from the second click
+        if game_view.count_click_inside_one_turn == 2 && self.count_click_inside_one_turn != 2 {
+            self.second_click_time = now;
+        }
+
+        self.vec_cards = game_view.vec_cards;
+        self.player_turn = game_view.player_turn;
+        self.count_click_inside_one_turn = game_view.count_click_inside_one_turn;
+        self.card_index_of_first_click = game_view.card_index_of_first_click;
+        self.card_index_of_second_click = game_view.card_index_of_second_click;
+        self.player1_points = game_view.player1_points;
+        self.player2_points = game_view.player2_points;
+
+        //the screen reader hears the same as in the other modes
+        let mut vec_announcements = Vec::new();
+        if let Some(card_number) = vec_pairs.last() {
+            vec_announcements.push(accessibility::pair_text(self, *card_number, true));
+        } else if !vec_flipped.is_empty() && self.count_click_inside_one_turn == 2 {
+            vec_announcements.push(accessibility::pair_text(self, 0, false));
+        }
+        if is_turn_changed && !self.is_game_over() {
+            vec_announcements.push(accessibility::turn_text(self));
+        }
+        if !vec_announcements.is_empty() {
+            self.announce(vec_announcements.join(" "));
+        }
+
+        for card_index in vec_flipped {
+            self.count_letter_flip(card_index);
+            if let Err(err) = self.play_card_sound(card_index) {
+                self.show_error(err);
+            }
+        }
+        if is_my_turn {
+            for card_number in vec_pairs {
+                self.record_pair_found(card_number);
+            }
+        }
+        if self.is_game_over() {
+            if let Err(err) = self.fire(GameEvent::AllCardsMatched) {
+                self.show_error(err);
+            }
+        }
+    }
+    ///key of the high scores table for the current game
+    fn high_score_key(&self) -> HighScoreKey {
+        HighScoreKey {
+            game_mode: self.game_mode.as_ref().to_string(),
+            grid_size: format!("{}x{}", self.settings.grid_columns, self.settings.grid_rows),
+            deck: self.settings.deck.clone(),
+            time_limit_s: self.settings.time_limit_s,
+        }
+    }
+    ///write the result of the finished game to the high scores in localStorage
+    fn record_high_score(&mut self) {
+        let date = String::from(js_sys::Date::new_0().to_iso_string());
+        //hot seat: both players played on this device, each of them has a result
+        let vec_player_numbers = if self.is_hot_seat() {
+            vec![1, 2]
+        } else {
+            vec![self.this_machine_player_number]
+        };
+        let vec_entries = vec_player_numbers
+            .into_iter()
+            .map(|player_number| HighScoreEntry {
+                count_all_clicks: self.player_clicks(player_number),
+                duration_ms: self.game_duration_ms,
+                points: if player_number == 1 {
+                    self.player1_points
+                } else {
+                    self.player2_points
+                },
+                date: date.clone(),
+                //alone the player number says nothing
+                player_number: if self.is_solo() { 0 } else { player_number },
+            })
+            .collect();
+        let key = self.high_score_key();
+        self.vec_high_score_ranks = self.high_scores.insert_all(&key, vec_entries);
+        high_scores::save(&self.high_scores);
+    }
+    ///the clicks of the player 1 or 2 that flipped a card
+    fn player_clicks(&self, player_number: usize) -> usize {
+        self.vec_player_clicks
+            .get(player_number.wrapping_sub(1))
+            .copied()
+            .unwrap_or(0)
+    }
+    ///one more click that flips a card, by the player whose turn it is
+    fn count_click(&mut self) {
+        self.count_all_clicks += 1;
+        if let Some(clicks) = self
+            .vec_player_clicks
+            .get_mut(self.player_turn.wrapping_sub(1))
+        {
+            *clicks += 1;
         }
     }
 }
@@ -386,16 +1169,20 @@ impl RootRenderingComponent {
             //Clippy wants this monstruosity instead of the simple rc.clone();
             rc: Rc::<std::cell::RefCell<GameData>>::clone(&rc),
         };
+        let error_overlay = ErrorOverlay {
+            rc: Rc::<std::cell::RefCell<GameData>>::clone(&rc),
+        };
         RootRenderingComponent {
             rc,
             players_and_scores,
             cached_rules_and_description,
+            error_overlay,
         }
     }
     ///The onclick event passed by javascript executes all the logic
     ///and changes only the fields of the Card Grid struct.
     ///That stuct is the only permanent data storage for later render the virtual dom.
-    fn card_on_click(&self, mut game_data: std::cell::RefMut<'_, GameData>) {
+    fn card_on_click(&self, mut game_data: std::cell::RefMut<'_, GameData>) -> Mem2Result<()> {
         //get this_click_card_index from game_data
         let this_click_card_index = if game_data.count_click_inside_one_turn == 1 {
             game_data.card_index_of_first_click
@@ -405,50 +1192,53 @@ impl RootRenderingComponent {
 
         if game_data.count_click_inside_one_turn == 1 || game_data.count_click_inside_one_turn == 2
         {
-            //region: audio play
-            //prepare the audio element with src filename of mp3
-            let audio_element = web_sys::HtmlAudioElement::new_with_src(
-                format!(
-                    "{}/sound/mem_sound_{:02}.mp3",
-                    game_data.content_folder_name,
-                    game_data
-                        .vec_cards
-                        .get(this_click_card_index)
-                        .expect("error this_click_card_index")
-                        .card_number_and_img_src
-                )
-                .as_str(),
-            );
-
-            //play() return a Promise in JSValue. That is too hard for me to deal with now.
-            audio_element
-                .expect("Error: HtmlAudioElement new.")
-                .play()
-                .expect("Error: HtmlAudioElement.play() ");
-            //endregion
+            //without sound the game can continue
+            if let Err(err) = game_data.play_card_sound(this_click_card_index) {
+                game_data.show_error(err);
+            }
+            game_data.count_letter_flip(this_click_card_index);
 
             //flip the card up
             game_data
                 .vec_cards
                 .get_mut(this_click_card_index)
-                .expect("error this_click_card_index")
+                .ok_or(Mem2Error::CardIndex(this_click_card_index))?
                 .status = CardStatusCardFace::UpTemporary;
+            let now = js_sys::Date::now();
+            game_data
+                .card_animations
+                .schedule(this_click_card_index, CardAnimation::FlipUp, now);
 
             if game_data.count_click_inside_one_turn == 2 {
                 //if is the second click, flip the card and then check for card match
+                //auto-flip counts from the second click
+                game_data.second_click_time = js_sys::Date::now();
 
                 //if the cards match, player get one point and continues another turn
-                if game_data
+                let x1 = game_data.card_index_of_first_click;
+                let x2 = game_data.card_index_of_second_click;
+                let card_number = game_data
                     .vec_cards
-                    .get(game_data.card_index_of_first_click)
-                    .expect("error game_data.card_index_of_first_click")
-                    .card_number_and_img_src
+                    .get(x1)
+                    .ok_or(Mem2Error::CardIndex(x1))?
+                    .card_number_and_img_src;
+                if card_number
                     == game_data
                         .vec_cards
-                        .get(game_data.card_index_of_second_click)
-                        .expect("error game_data.card_index_of_second_click")
+                        .get(x2)
+                        .ok_or(Mem2Error::CardIndex(x2))?
                         .card_number_and_img_src
                 {
+                    //the learning progress is of the child on this device
+                    if game_data.player_turn == game_data.this_machine_player_number {
+                        game_data.record_pair_found(card_number);
+                    }
+                    //the pair celebrates after the flip
+                    game_data.card_animations.schedule_together(
+                        &[x1, x2],
+                        CardAnimation::Match,
+                        now,
+                    );
                     //give points
                     if game_data.player_turn == 1 {
                         game_data.player1_points += 1;
@@ -457,61 +1247,199 @@ impl RootRenderingComponent {
                     }
 
                     // the two cards matches. make them permanent FaceUp
-                    let x1 = game_data.card_index_of_first_click;
-                    let x2 = game_data.card_index_of_second_click;
                     game_data
                         .vec_cards
                         .get_mut(x1)
-                        .expect("error game_data.card_index_of_first_click")
+                        .ok_or(Mem2Error::CardIndex(x1))?
                         .status = CardStatusCardFace::UpPermanently;
                     game_data
                         .vec_cards
                         .get_mut(x2)
-                        .expect("error game_data.card_index_of_second_click")
+                        .ok_or(Mem2Error::CardIndex(x2))?
                         .status = CardStatusCardFace::UpPermanently;
                     game_data.count_click_inside_one_turn = 0;
+
+                    //the match rule decides who plays next
+                    let mut announcement = accessibility::pair_text(&game_data, card_number, true);
+                    //alone there is nobody to pass the turn to
+                    if let (MatchRule::PassTurn, false) =
+                        (game_data.settings.match_rule, game_data.is_solo())
+                    {
+                        game_data.player_turn = if game_data.player_turn == 1 { 2 } else { 1 };
+                        //hot seat: the device belongs to the player whose turn it is
+                        if let GameMode::HotSeat = game_data.game_mode {
+                            game_data.this_machine_player_number = game_data.player_turn;
+                        }
+                        if !game_data.is_game_over() {
+                            announcement.push(' ');
+                            announcement.push_str(&accessibility::turn_text(&game_data));
+                        }
+                    }
+                    game_data.announce(announcement);
+
+                    if game_data.is_game_over() {
+                        game_data.fire(GameEvent::AllCardsMatched)?;
+                    }
+                } else {
+                    //the wrong pair shakes after the flip
+                    game_data.card_animations.schedule_together(
+                        &[x1, x2],
+                        CardAnimation::Mismatch,
+                        now,
+                    );
+                    let announcement = accessibility::pair_text(&game_data, card_number, false);
+                    game_data.announce(announcement);
+                }
+            }
+        }
+        Ok(())
+    }
+    ///the player flips the card with a click or with the keyboard
+    fn flip_card_of_player(&self, this_click_card_index: usize) {
+        //this game_data mutable reference is dropped on the end of the function
+        let mut game_data = self.rc.borrow_mut();
+        game_data.focused_card_index = this_click_card_index;
+        //the click on grid is allowed only when is the turn of this player
+        if game_data.game_state != GameState::Play
+            || game_data.player_turn != game_data.this_machine_player_number
+        {
+            return;
+        }
+
+        //the card in the middle of its animation waits
+        if game_data
+            .card_animations
+            .is_busy(this_click_card_index, js_sys::Date::now())
+        {
+            return;
+        }
+
+        //server-authoritative: only the server flips the card
+        if let GameMode::ServerAuthoritative = game_data.game_mode {
+            let msg = WsMessage::AuthFlip {
+                ws_client_instance: game_data.my_ws_client_instance,
+                card_index: this_click_card_index,
+            };
+            if let Err(err) = game_data.send_ws_message(&msg) {
+                game_data.show_error(err);
+            }
+            return;
+        }
+
+        //click is usefull only od facedown cards
+        let status = match game_data.vec_cards.get(this_click_card_index) {
+            None => return,
+            Some(card) => card.status,
+        };
+        if let CardStatusCardFace::Down = status {
+            //the begining of the turn is count_click_inside_one_turn=0
+            //on click imediately increase that. So first click is 1 and second click is 2.
+            //all other clicks on the grid are not usable.
+            game_data.count_click_inside_one_turn += 1;
+
+            if game_data.count_click_inside_one_turn == 1 {
+                game_data.card_index_of_first_click = this_click_card_index;
+                game_data.card_index_of_second_click = 0;
+                game_data.count_click();
+            } else if game_data.count_click_inside_one_turn == 2 {
+                game_data.card_index_of_second_click = this_click_card_index;
+                game_data.count_click();
+            } else {
+                //nothing
+            }
+
+            //region: send WsMessage over websocket
+            if game_data.is_multiplayer() {
+                let msg = WsMessage::PlayerClick {
+                    ws_client_instance: game_data.my_ws_client_instance,
+                    card_index: this_click_card_index,
+                    count_click_inside_one_turn: game_data.count_click_inside_one_turn,
+                };
+                if let Err(err) = game_data.send_game_message(msg) {
+                    game_data.show_error(err);
                 }
             }
+            //endregion
+            //game_data is moved and dropped inside card_on_click
+            if let Err(err) = self.card_on_click(game_data) {
+                self.rc.borrow_mut().show_error(err);
+            }
         }
     }
     ///fn on change for both click and we msg.
-    fn take_turn(&self, mut game_data: std::cell::RefMut<'_, GameData>) {
-        game_data.player_turn = if game_data.player_turn == 1 { 2 } else { 1 };
+    ///The vdom renders again when the cards turn down after the mismatch shake.
+    fn take_turn(
+        &self,
+        mut game_data: std::cell::RefMut<'_, GameData>,
+        vdom: &dodrio::VdomWeak,
+    ) -> Mem2Result<()> {
+        //alone the same player flips the cards back and plays again
+        if !game_data.is_solo() {
+            game_data.player_turn = if game_data.player_turn == 1 { 2 } else { 1 };
+        }
 
         //click on Change button closes first and second card
         let x1 = game_data.card_index_of_first_click;
         let x2 = game_data.card_index_of_second_click;
+        //the cards show their faces until they turn down after the mismatch shake
+        let vec_faces = [x1, x2]
+            .iter()
+            .map(|card_index| {
+                game_data
+                    .vec_cards
+                    .get(*card_index)
+                    .map(|card| (*card_index, card.card_number_and_img_src))
+                    .ok_or(Mem2Error::CardIndex(*card_index))
+            })
+            .collect::<Mem2Result<Vec<(usize, usize)>>>()?;
+        let now = js_sys::Date::now();
+        game_data
+            .card_animations
+            .schedule_flip_down(&vec_faces, now);
+        schedule_render_at_face_change(&game_data, vdom);
         game_data
             .vec_cards
             .get_mut(x1)
-            .expect("error game_data.card_index_of_first_click ")
+            .ok_or(Mem2Error::CardIndex(x1))?
             .status = CardStatusCardFace::Down;
         game_data
             .vec_cards
             .get_mut(x2)
-            .expect("error game_data.card_index_of_second_click")
+            .ok_or(Mem2Error::CardIndex(x2))?
             .status = CardStatusCardFace::Down;
         game_data.card_index_of_first_click = 0;
         game_data.card_index_of_second_click = 0;
         game_data.count_click_inside_one_turn = 0;
-    }
-    ///get spelling from session storage
-    fn get_spelling(&self) -> Spelling {
-        let x: Option<Spelling> = session_storage()
-            .get("Spelling")
-            .ok()
-            .and_then(|opt| opt)
-            .and_then(|json| serde_json::from_str(&json).ok());
-
-        x.expect("error session_storage().get('Spelling')")
+        let announcement = accessibility::turn_text(&game_data);
+        game_data.announce(announcement);
+        Ok(())
     }
 }
 //endregion
 
-/// Get the top-level window's session storage.
-pub fn session_storage() -> web_sys::Storage {
-    let window = web_sys::window().expect("error: web_sys::window");
-    window.session_storage().unwrap_throw().unwrap_throw()
+///get spelling from session storage. It is there after text.json is fetched.
+fn get_spelling() -> Mem2Result<Spelling> {
+    session_storage()?
+        .get("Spelling")
+        .ok()
+        .and_then(|opt| opt)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or(Mem2Error::SpellingNotLoaded)
+}
+
+/// Get the top-level window's session storage. It can be disabled in the browser.
+pub fn session_storage() -> Mem2Result<web_sys::Storage> {
+    web_sys::window()
+        .ok_or(Mem2Error::NoWindow)?
+        .session_storage()
+        .ok()
+        .and_then(|opt| opt)
+        .ok_or(Mem2Error::StorageUnavailable)
+}
+
+/// Get the top-level window's local storage. It can be disabled in the browser.
+pub fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|window| window.local_storage().ok().and_then(|opt| opt))
 }
 
 //region: `Render` trait implementation on CardGrid struct
@@ -546,41 +1474,73 @@ impl Render for RootRenderingComponent {
             use dodrio::builder::*;
             //this game_data mutable reference is dropped on the end of the function
             let game_data = cr_gr.rc.borrow();
+            //the labels tell the letters of the face up cards
+            let spelling = get_spelling().ok();
+            //a new smaller grid can lose the focused card
+            let focused_card_index = if game_data.focused_card_index < game_data.vec_cards.len() {
+                game_data.focused_card_index
+            } else {
+                1
+            };
 
             let mut vec_grid_item_bump = Vec::new();
-            for x in 1..=16 {
-                let index: usize = x;
+            for index in 1..game_data.vec_cards.len() {
                 //region: prepare variables and closures for inserting into vdom
-                let img_src = match game_data.vec_cards.get(index).expect("error index").status {
-                    CardStatusCardFace::Down => bumpalo::format!(in bump, "{}/{}",
-                                                game_data.content_folder_name,
-                                                SRC_FOR_CARD_FACE_DOWN)
+                let card = match game_data.vec_cards.get(index) {
+                    Some(card) => card,
+                    None => continue,
+                };
+                //a card turned down shows its face until the mismatch shake ends and the flip down starts
+                let face_before_flip_down = game_data
+                    .card_animations
+                    .face_before_flip_down(card.card_index_and_id, js_sys::Date::now());
+                let img_src = match (card.status, face_before_flip_down) {
+                    (CardStatusCardFace::Down, Some(card_number)) => {
+                        bumpalo::format!(in bump, "{}/img/mem_image_{:02}.png",
+                        game_data.settings.deck,
+                                card_number
+                        )
+                        .into_bump_str()
+                    }
+                    //the card back of the theme or of the deck
+                    (CardStatusCardFace::Down, None) => bumpalo::format!(in bump, "{}",
+                        theme::card_back_src(game_data.theme, &game_data.settings.deck, SRC_FOR_CARD_FACE_DOWN))
                     .into_bump_str(),
-                    CardStatusCardFace::UpTemporary | CardStatusCardFace::UpPermanently => {
+                    (CardStatusCardFace::UpTemporary, _) | (CardStatusCardFace::UpPermanently, _) => {
                         bumpalo::format!(in bump, "{}/img/mem_image_{:02}.png",
-                        game_data.content_folder_name,
-                                game_data
-                                    .vec_cards
-                                    .get(index)
-                                    .expect("error index")
-                                    .card_number_and_img_src
+                        game_data.settings.deck,
+                                card.card_number_and_img_src
                         )
                         .into_bump_str()
                     }
                 };
 
                 let img_id =
-                    bumpalo::format!(in bump, "img{:02}",game_data.vec_cards.get(index).expect("error index").card_index_and_id)
-                        .into_bump_str();
-
-                let opacity = if img_src
-                    == format!(
-                        "{}/{}",
-                        game_data.content_folder_name, SRC_FOR_CARD_FACE_DOWN
-                    ) {
-                    bumpalo::format!(in bump, "opacity:{}", 0.2).into_bump_str()
+                    bumpalo::format!(in bump, "img{:02}",card.card_index_and_id).into_bump_str();
+
+                let opacity =
+                    if card.status == CardStatusCardFace::Down && face_before_flip_down.is_none() {
+                        game_data.color_scheme.face_down_opacity(game_data.theme)
+                    } else {
+                        1.0
+                    };
+                //the running animation of the card
+                let style = bumpalo::format!(in bump, "opacity:{};{}",
+                    opacity,
+                    game_data
+                        .card_animations
+                        .css_style(card.card_index_and_id, js_sys::Date::now())
+                        .unwrap_or_default())
+                .into_bump_str();
+                let card_index = card.card_index_and_id;
+                let label = bumpalo::format!(in bump, "{}",
+                    accessibility::card_label(card, spelling.as_ref()))
+                .into_bump_str();
+                //only the focused card is in the tab order
+                let tabindex = if card_index == focused_card_index {
+                    "0"
                 } else {
-                    bumpalo::format!(in bump, "opacity:{}", 1).into_bump_str()
+                    "-1"
                 };
                 //endregion
 
@@ -590,7 +1550,12 @@ impl Render for RootRenderingComponent {
                     .children([img(bump)
                         .attr("src", img_src)
                         .attr("id", img_id)
-                        .attr("style", opacity)
+                        .attr("class", "card")
+                        .attr("style", style)
+                        .attr("alt", label)
+                        .attr("aria-label", label)
+                        .attr("role", "button")
+                        .attr("tabindex", tabindex)
                         //on click needs a code Closure in Rust. Dodrio and wasm-bindgen
                         //generate the javascript code to call it properly.
                         .on("click", move |root, vdom, event| {
@@ -600,73 +1565,50 @@ impl Render for RootRenderingComponent {
                             //The method render will later use that for rendering the new html.
                             let root_rendering_component =
                                 root.unwrap_mut::<RootRenderingComponent>();
-                            //this game_data mutable reference is dropped on the end of the function
-                            let mut game_data = root_rendering_component.rc.borrow_mut();
-                            //the click on grid is allowed only when is the turn of this player
-                            if (game_data.game_state.as_ref() == GameState::Play.as_ref()
-                                && game_data.player_turn == 1
-                                && game_data.this_machine_player_number == 1)
-                                || (game_data.game_state.as_ref() == GameState::Play.as_ref()
-                                    && game_data.player_turn == 2
-                                    && game_data.this_machine_player_number == 2)
+                            // If the event's target is our image...
+                            let img = match event
+                                .target()
+                                .and_then(|t| t.dyn_into::<web_sys::HtmlImageElement>().ok())
                             {
-                                // If the event's target is our image...
-                                let img = match event
-                                    .target()
-                                    .and_then(|t| t.dyn_into::<web_sys::HtmlImageElement>().ok())
-                                {
+                                None => return,
+                                //?? Don't understand what this does. The original was written for Input element.
+                                Some(input) => input,
+                            };
+
+                            //id attribute of image html element is prefixed with img ex. "img12"
+                            let this_click_card_index =
+                                match img.id().get(3..).and_then(|id| id.parse::<usize>().ok()) {
                                     None => return,
-                                    //?? Don't understand what this does. The original was written for Input element.
-                                    Some(input) => input,
+                                    Some(card_index) => card_index,
                                 };
-
-                                //id attribute of image html element is prefixed with img ex. "img12"
-                                let this_click_card_index =
-                                    (img.id().get(3..).expect("error slicing"))
-                                        .parse::<usize>()
-                                        .expect("error parse img id to usize");
-
-                                //click is usefull only od facedown cards
-                                if let CardStatusCardFace::Down = game_data
-                                    .vec_cards
-                                    .get(this_click_card_index)
-                                    .expect("error this_click_card_index")
-                                    .status
-                                {
-                                    //the begining of the turn is count_click_inside_one_turn=0
-                                    //on click imediately increase that. So first click is 1 and second click is 2.
-                                    //all other clicks on the grid are not usable.
-                                    game_data.count_click_inside_one_turn += 1;
-
-                                    if game_data.count_click_inside_one_turn == 1 {
-                                        game_data.card_index_of_first_click = this_click_card_index;
-                                        game_data.card_index_of_second_click = 0;
-                                        game_data.count_all_clicks += 1;
-                                    } else if game_data.count_click_inside_one_turn == 2 {
-                                        game_data.card_index_of_second_click =
-                                            this_click_card_index;
-                                        game_data.count_all_clicks += 1;
-                                    } else {
-                                        //nothing
-                                    }
-
-                                    //region: send WsMessage over websocket
-                                    game_data
-                                        .ws
-                                        .send_with_str(
-                                            &serde_json::to_string(&WsMessage::PlayerClick {
-                                                ws_client_instance: game_data.my_ws_client_instance,
-                                                card_index: this_click_card_index,
-                                                count_click_inside_one_turn: game_data
-                                                    .count_click_inside_one_turn,
-                                            })
-                                            .expect("error sending PlayerClick"),
-                                        )
-                                        .expect("Failed to send PlayerClick");
-                                    //endregion
-                                    root_rendering_component.card_on_click(game_data);
-                                }
-                                // Finally, re-render the component on the next animation frame.
+                            root_rendering_component.flip_card_of_player(this_click_card_index);
+                            // Finally, re-render the component on the next animation frame.
+                            vdom.schedule_render();
+                        })
+                        //the arrow keys move the focus, Enter and Space flip the card
+                        .on("keydown", move |root, vdom, event| {
+                            let key = match event.dyn_ref::<web_sys::KeyboardEvent>() {
+                                None => return,
+                                Some(keyboard_event) => keyboard_event.key(),
+                            };
+                            let root_rendering_component =
+                                root.unwrap_mut::<RootRenderingComponent>();
+                            if key == "Enter" || key == " " {
+                                event.prevent_default();
+                                root_rendering_component.flip_card_of_player(card_index);
+                                vdom.schedule_render();
+                                return;
+                            }
+                            let mut game_data = root_rendering_component.rc.borrow_mut();
+                            if let Some(next_card_index) = mem2_engine::next_card_index(
+                                card_index,
+                                &key,
+                                game_data.settings.grid_columns,
+                                game_data.vec_cards.len().saturating_sub(1),
+                            ) {
+                                event.prevent_default();
+                                game_data.focused_card_index = next_card_index;
+                                accessibility::focus_card(next_card_index);
                                 vdom.schedule_render();
                             }
                         })
@@ -689,27 +1631,52 @@ impl Render for RootRenderingComponent {
             //enought space on smartphones
             if game_data.card_index_of_first_click != 0 || game_data.card_index_of_second_click != 0
             {
-                //if the two opened card match use green else use red color
-                let color; //haha variable does not need to be mutable. Great !
-
-                if game_data
-                    .vec_cards
-                    .get(game_data.card_index_of_first_click)
-                    .expect("error index")
-                    .card_number_and_img_src
-                    == game_data
+                //if the two opened card match use green else use red color. Other schemes add icons.
+                let status;
+                let card_number = |card_index: usize| {
+                    game_data
                         .vec_cards
-                        .get(game_data.card_index_of_second_click)
-                        .expect("error index")
-                        .card_number_and_img_src
+                        .get(card_index)
+                        .map_or(0, |card| card.card_number_and_img_src)
+                };
+                //until the spelling is loaded the header shows nothing
+                let spelling = get_spelling();
+                let spelling_name = |card_index: usize| {
+                    spelling
+                        .as_ref()
+                        .ok()
+                        .and_then(|spelling| spelling.name.get(card_number(card_index)))
+                        .map_or("", String::as_str)
+                };
+                //the dots and dashes of the last flipped card light up with its synthesized sound
+                let card_index_last_flipped = if game_data.card_index_of_second_click != 0 {
+                    game_data.card_index_of_second_click
+                } else {
+                    game_data.card_index_of_first_click
+                };
+                let is_animated = game_data.audio.is_synthesized() && !game_data.audio.is_muted;
+                let div_morse = |card_index: usize| {
+                    morse_view::div_morse_code(
+                        mem2_engine::morse::card_letter(card_number(card_index)),
+                        if is_animated && card_index == card_index_last_flipped {
+                            Some(game_data.audio.wpm)
+                        } else {
+                            None
+                        },
+                        bump,
+                    )
+                };
+
+                if card_number(game_data.card_index_of_first_click)
+                    == card_number(game_data.card_index_of_second_click)
                 {
-                    color = "green";
+                    status = Status::Positive;
                 } else if game_data.card_index_of_first_click == 0
                     || game_data.card_index_of_second_click == 0
                 {
-                    color = "yellow";
+                    status = Status::Caution;
                 } else {
-                    color = "red";
+                    status = Status::Negative;
                 }
 
                 {
@@ -718,7 +1685,7 @@ impl Render for RootRenderingComponent {
                 .attr("class", "grid_container_header")
                 .attr(
                     "style",
-                    bumpalo::format!(in bump, "grid-template-columns: auto auto; color:{}",color)
+                    bumpalo::format!(in bump, "grid-template-columns: auto auto; {}", indicator::css(&game_data, status))
                         .into_bump_str(),
                 )
                 .children([
@@ -726,23 +1693,17 @@ impl Render for RootRenderingComponent {
                         .attr("class", "grid_item")
                         .attr("style", "text-align: left;")
                         .children([text(
-bumpalo::format!(in bump, "{}",
- root_rendering_component.get_spelling().name.get(game_data.vec_cards.get(game_data.card_index_of_first_click).expect("error index")
-                                .card_number_and_img_src).expect("error index")
-)
-                        .into_bump_str(),
-                        )])
+                            indicator::label(&game_data, status, spelling_name(game_data.card_index_of_first_click), bump),
+                        ),
+                        div_morse(game_data.card_index_of_first_click)])
                         .finish(),
                     div(bump)
                         .attr("class", "grid_item")
                         .attr("style", "text-align: right;")
                         .children([text(
-                            bumpalo::format!(in bump, "{}",
-                            root_rendering_component.get_spelling().name.get(game_data.vec_cards.get(game_data.card_index_of_second_click).expect("error index")
-                                .card_number_and_img_src).expect("error index")
-                                )
-                        .into_bump_str(),
-                        )])
+                            indicator::label(&game_data, status, spelling_name(game_data.card_index_of_second_click), bump),
+                        ),
+                        div_morse(game_data.card_index_of_second_click)])
                         .finish(),
                 ])
                 .finish()
@@ -775,84 +1736,310 @@ bumpalo::format!(in bump, "{}",
             if let GameState::Start = game_data.game_state {
                 // 1S Ask Player2 to play!
                 console::log_1(&"GameState::Start".into());
-                //return Ask Player2 to play!
-                h3(bump)
-                    .attr("id", "ws_elem")
-                    .attr("style", "color:green;")
-                    .children([text(
-                        //show Ask Player2 to Play!
-                        bumpalo::format!(in bump, "Ask other Player to play! {}", "")
-                            .into_bump_str(),
-                    )])
-                    .on("click", move |root, vdom, _event| {
-                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
-                        //this game_data mutable reference is dropped on the end of the function
-                        let mut game_data = root_rendering_component.rc.borrow_mut();
-                        //region: send WsMessage over websocket
-                        game_data.this_machine_player_number = 1;
-                        game_data.game_state = GameState::Asking;
-                        game_data
-                            .ws
-                            .send_with_str(
-                                &serde_json::to_string(&WsMessage::WantToPlay {
+                //return Ask Player2 to play! or play on this device
+                div(bump)
+                    .children([
+                        div_invitation_outcome(&game_data, bump),
+                        lobby::div_lobby(&game_data, bump),
+                        settings::div_my_settings(&game_data, bump),
+                        h3(bump)
+                            .attr("style", indicator::style(&game_data, Status::Action, bump))
+                            .children([text(
+                                bumpalo::format!(in bump, "Play on this device! {}", "")
+                                    .into_bump_str(),
+                            )])
+                            .on("click", move |root, vdom, _event| {
+                                let root_rendering_component =
+                                    root.unwrap_mut::<RootRenderingComponent>();
+                                //this game_data mutable reference is dropped on the end of the function
+                                let mut game_data = root_rendering_component.rc.borrow_mut();
+                                //hot seat: this machine is always the player whose turn it is
+                                if let Err(err) = game_data.fire(GameEvent::PlayHotSeat) {
+                                    game_data.show_error(err);
+                                    vdom.schedule_render();
+                                    return;
+                                }
+                                let settings = game_data.my_settings.clone();
+                                game_data.apply_settings(settings);
+                                game_data.game_mode = GameMode::HotSeat;
+                                game_data.this_machine_player_number = game_data.player_turn;
+                                vdom.schedule_render();
+                            })
+                            .finish(),
+                        h3(bump)
+                            .attr("style", indicator::style(&game_data, Status::Action, bump))
+                            .children([text(
+                                bumpalo::format!(in bump, "Play alone! {}", "").into_bump_str(),
+                            )])
+                            .on("click", move |root, vdom, _event| {
+                                let root_rendering_component =
+                                    root.unwrap_mut::<RootRenderingComponent>();
+                                //this game_data mutable reference is dropped on the end of the function
+                                let mut game_data = root_rendering_component.rc.borrow_mut();
+                                if let Err(err) = game_data.fire(GameEvent::PlaySolo) {
+                                    game_data.show_error(err);
+                                    vdom.schedule_render();
+                                    return;
+                                }
+                                let settings = game_data.my_settings.clone();
+                                game_data.apply_settings(settings);
+                                game_data.game_mode = GameMode::Solo;
+                                game_data.this_machine_player_number = 1;
+                                vdom.schedule_render();
+                            })
+                            .finish(),
+                        h3(bump)
+                            .attr("style", indicator::style(&game_data, Status::Action, bump))
+                            .children([text(
+                                bumpalo::format!(in bump, "Play the quiz! {}", "").into_bump_str(),
+                            )])
+                            .on("click", move |root, vdom, _event| {
+                                let root_rendering_component =
+                                    root.unwrap_mut::<RootRenderingComponent>();
+                                //this game_data mutable reference is dropped on the end of the function
+                                let mut game_data = root_rendering_component.rc.borrow_mut();
+                                //the quiz uses the deck of my settings
+                                let settings = game_data.my_settings.clone();
+                                game_data.apply_settings(settings);
+                                if let Err(err) = game_data.fire(GameEvent::StartQuiz) {
+                                    game_data.show_error(err);
+                                }
+                                vdom.schedule_render();
+                            })
+                            .finish(),
+                        h3(bump)
+                            .attr("style", indicator::style(&game_data, Status::Action, bump))
+                            .children([text(
+                                bumpalo::format!(in bump, "Play with the server rules! {}", "")
+                                    .into_bump_str(),
+                            )])
+                            .on("click", move |root, vdom, _event| {
+                                let root_rendering_component =
+                                    root.unwrap_mut::<RootRenderingComponent>();
+                                //this game_data mutable reference is dropped on the end of the function
+                                let mut game_data = root_rendering_component.rc.borrow_mut();
+                                //the server pairs this client with the next one that asks
+                                if let Err(err) = game_data.fire(GameEvent::JoinServer) {
+                                    game_data.show_error(err);
+                                    vdom.schedule_render();
+                                    return;
+                                }
+                                game_data.game_mode = GameMode::ServerAuthoritative;
+                                let msg = WsMessage::AuthJoin {
                                     ws_client_instance: game_data.my_ws_client_instance,
-                                })
-                                .expect("error sending test"),
-                            )
-                            .expect("Failed to send");
-                        //endregion
-                        vdom.schedule_render();
-                    })
+                                    settings: game_data.settings.clone(),
+                                };
+                                if let Err(err) = game_data.send_ws_message(&msg) {
+                                    game_data.show_error(err);
+                                }
+                                vdom.schedule_render();
+                            })
+                            .finish(),
+                    ])
                     .finish()
             } else if let GameState::Asking = game_data.game_state {
-                //return wait for the other player
-                div_wait_for_other_player(bump)
+                //return the pending invitation with the countdown and cancel
+                div(bump)
+                    .children([
+                        h3(bump)
+                            .attr("id", "ws_elem")
+                            .attr("style", indicator::style(&game_data, Status::Notice, bump))
+                            .children([text(
+                                bumpalo::format!(in bump, "Invitation pending. It expires in {} s.", game_data.invitation_seconds_left())
+                                    .into_bump_str(),
+                            )])
+                            .finish(),
+                        div_settings_proposal(&game_data, bump),
+                        h3(bump)
+                            .attr("style", indicator::style(&game_data, Status::Action, bump))
+                            .children([text("Cancel the invitation!")])
+                            .on("click", move |root, vdom, _event| {
+                                let root_rendering_component =
+                                    root.unwrap_mut::<RootRenderingComponent>();
+                                //this game_data mutable reference is dropped on the end of the function
+                                let mut game_data = root_rendering_component.rc.borrow_mut();
+                                game_data.end_invitation(GameEvent::CancelInvite, None);
+                                vdom.schedule_render();
+                            })
+                            .finish(),
+                    ])
+                    .finish()
             } else if let GameState::Asked = game_data.game_state {
                 // 2S Click here to Accept play!
                 console::log_1(&"GameState::Asked".into());
-                //return Click here to Accept play
+                //return Click here to Accept play, decline or counter-propose
+                let mut vec_children = vec![
+                    div_settings_proposal(&game_data, bump),
+                    h3(bump)
+                        .attr("id", "ws_elem")
+                        .attr("style", indicator::style(&game_data, Status::Action, bump))
+                        .children([text(
+                            //show Ask Player2 to Play!
+                            bumpalo::format!(in bump, "Click here to Accept play! {}", "")
+                                .into_bump_str(),
+                        )])
+                        .on("click", move |root, vdom, _event| {
+                            let root_rendering_component =
+                                root.unwrap_mut::<RootRenderingComponent>();
+                            //this game_data mutable reference is dropped on the end of the function
+                            let mut game_data = root_rendering_component.rc.borrow_mut();
+                            if let Err(err) = game_data.fire(GameEvent::AcceptPlay) {
+                                game_data.show_error(err);
+                                vdom.schedule_render();
+                                return;
+                            }
+                            //both players build the game from the agreed settings
+                            let settings = game_data.settings_proposal.clone();
+                            game_data.apply_settings(settings);
+                            //region: send WsMessage over websocket
+                            game_data.this_machine_player_number = 2;
+
+                            //send the vector of cards because both players need cards in the same location.
+                            let wire_format =
+                                game_data.my_wire_format.agree(game_data.wire_format_proposal);
+                            game_data.my_nonce = new_nonce();
+                            let msg = WsMessage::AcceptPlay {
+                                ws_client_instance: game_data.my_ws_client_instance,
+                                vec_cards: game_data.vec_cards.clone(),
+                                settings: game_data.settings.clone(),
+                                wire_format,
+                                nonce: game_data.my_nonce,
+                            };
+                            if let Err(err) = game_data.send_ws_message(&msg) {
+                                game_data.show_error(err);
+                            }
+                            //the accept is the last message in json and the last one not sealed
+                            game_data.wire_format = wire_format;
+                            //the other player proposed, so he is player 1
+                            let key = SessionKey::derive(
+                                &game_data.room_secret,
+                                (game_data.other_ws_client_instance, game_data.nonce_proposal),
+                                (game_data.my_ws_client_instance, game_data.my_nonce),
+                            );
+                            game_data.peer_session = Some(PeerSession::new(
+                                key,
+                                game_data.my_ws_client_instance,
+                                game_data.other_ws_client_instance,
+                            ));
+                            //endregion
+                            vdom.schedule_render();
+                        })
+                        .finish(),
+                    h3(bump)
+                        .attr("style", indicator::style(&game_data, Status::Negative, bump))
+                        .children([text(
+                            bumpalo::format!(in bump, "Decline! The invitation expires in {} s.", game_data.invitation_seconds_left())
+                                .into_bump_str(),
+                        )])
+                        .on("click", move |root, vdom, _event| {
+                            let root_rendering_component =
+                                root.unwrap_mut::<RootRenderingComponent>();
+                            //this game_data mutable reference is dropped on the end of the function
+                            let mut game_data = root_rendering_component.rc.borrow_mut();
+                            let msg = WsMessage::DeclinePlay {
+                                ws_client_instance: game_data.my_ws_client_instance,
+                                other_ws_client_instance: game_data.other_ws_client_instance,
+                            };
+                            if let Err(err) = game_data.send_ws_message(&msg) {
+                                game_data.show_error(err);
+                            }
+                            game_data.end_invitation(GameEvent::DeclinePlay, None);
+                            vdom.schedule_render();
+                        })
+                        .finish(),
+                    settings::div_my_settings(&game_data, bump),
+                ];
+                //counter-propose only different settings
+                if game_data.my_settings != game_data.settings_proposal {
+                    vec_children.push(
+                        h3(bump)
+                            .attr("style", indicator::style(&game_data, Status::Action, bump))
+                            .children([text("Counter-propose my settings!")])
+                            .on("click", move |root, vdom, _event| {
+                                let root_rendering_component =
+                                    root.unwrap_mut::<RootRenderingComponent>();
+                                //this game_data mutable reference is dropped on the end of the function
+                                let mut game_data = root_rendering_component.rc.borrow_mut();
+                                if let Err(err) = game_data.fire(GameEvent::CounterPropose) {
+                                    game_data.show_error(err);
+                                    vdom.schedule_render();
+                                    return;
+                                }
+                                //now I wait for the answer like the player that asks
+                                game_data.this_machine_player_number = 1;
+                                game_data.settings_proposal = game_data.my_settings.clone();
+                                game_data.my_nonce = new_nonce();
+                                let msg = WsMessage::CounterPropose {
+                                    ws_client_instance: game_data.my_ws_client_instance,
+                                    other_ws_client_instance: game_data.other_ws_client_instance,
+                                    settings: game_data.settings_proposal.clone(),
+                                    wire_format: game_data.my_wire_format,
+                                    nonce: game_data.my_nonce,
+                                };
+                                if let Err(err) = game_data.send_ws_message(&msg) {
+                                    game_data.show_error(err);
+                                }
+                                vdom.schedule_render();
+                            })
+                            .finish(),
+                    );
+                }
+                div(bump).children(vec_children).finish()
+            } else if let GameState::GameOver = game_data.game_state {
+                //return game over with the high scores
+                div(bump)
+                    .children([
+                        h3(bump)
+                            .attr("id", "ws_elem")
+                            .attr("style", indicator::style(&game_data, Status::Action, bump))
+                            .children([text(if game_data.is_solo() {
+                                bumpalo::format!(in bump, "Game over! points: {} clicks: {}",
+                                game_data.player1_points,
+                                game_data.count_all_clicks)
+                                .into_bump_str()
+                            } else {
+                                bumpalo::format!(in bump, "Game over! player1: {} player2: {}",
+                                game_data.player1_points,
+                                game_data.player2_points)
+                                .into_bump_str()
+                            })])
+                            .finish(),
+                        high_scores::div_high_scores(&game_data, bump),
+                        learning::div_letter_progress(&game_data, bump),
+                    ])
+                    .finish()
+            } else if game_data.count_click_inside_one_turn >= 2 && game_data.is_solo() {
+                //return the cards that don't match go back down on click
                 h3(bump)
                     .attr("id", "ws_elem")
-                    .attr("style", "color:green;")
+                    .attr("style", indicator::style(&game_data, Status::Action, bump))
                     .children([text(
-                        //show Ask Player2 to Play!
-                        bumpalo::format!(in bump, "Click here to Accept play! {}", "")
+                        bumpalo::format!(in bump, "Click here to flip the cards back !{}", "")
                             .into_bump_str(),
                     )])
                     .on("click", move |root, vdom, _event| {
                         let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
-                        //this game_data mutable reference is dropped on the end of the function
-                        let mut game_data = root_rendering_component.rc.borrow_mut();
-                        //region: send WsMessage over websocket
-                        game_data.this_machine_player_number = 2;
-                        game_data.player_turn = 1;
-                        game_data.game_state = GameState::Play;
-
-                        game_data
-                            .ws
-                            .send_with_str(
-                                &serde_json::to_string(&WsMessage::AcceptPlay {
-                                    ws_client_instance: game_data.my_ws_client_instance,
-                                    //send the vector of cards because both players need cards in the same location.
-                                    card_grid_data: serde_json::to_string(&game_data.vec_cards)
-                                        .expect("error serde_json"),
-                                })
-                                .expect("error sending test"),
-                            )
-                            .expect("Failed to send");
-                        //endregion
+                        let game_data = root_rendering_component.rc.borrow_mut();
+                        //game_data is moved and dropped inside take_turn
+                        if let Err(err) = root_rendering_component.take_turn(game_data, &vdom) {
+                            root_rendering_component.rc.borrow_mut().show_error(err);
+                        }
                         vdom.schedule_render();
                     })
                     .finish()
+            } else if game_data.count_click_inside_one_turn >= 2 && game_data.is_hot_seat() {
+                //return the interstitial: pass the device to the other player.
+                //Multiplayer and server-authoritative games wait for the other machine.
+                div_pass_the_device(&game_data, bump)
             } else if game_data.count_click_inside_one_turn >= 2 {
                 if game_data.this_machine_player_number == game_data.player_turn {
                     //return wait for the other player
-                    div_wait_for_other_player(bump)
+                    div_wait_for_other_player(&game_data, bump)
                 } else {
                     //return Click here to take your turn
                     h3(bump)
                         .attr("id", "ws_elem")
-                        .attr("style", "color:green;")
+                        .attr("style", indicator::style(&game_data, Status::Action, bump))
                         .children([text(
                             bumpalo::format!(in bump, "Click here to take your turn !{}", "")
                                 .into_bump_str(),
@@ -862,19 +2049,30 @@ bumpalo::format!(in bump, "{}",
                                 root.unwrap_mut::<RootRenderingComponent>();
                             //this game_data mutable reference is dropped on the end of the function
                             //clippy is wrong about dropping the mut. I need it.
-                            let game_data = root_rendering_component.rc.borrow_mut();
+                            let mut game_data = root_rendering_component.rc.borrow_mut();
+                            //server-authoritative: only the server flips the cards back
+                            if let GameMode::ServerAuthoritative = game_data.game_mode {
+                                let msg = WsMessage::AuthTakeTurn {
+                                    ws_client_instance: game_data.my_ws_client_instance,
+                                };
+                                if let Err(err) = game_data.send_ws_message(&msg) {
+                                    game_data.show_error(err);
+                                    vdom.schedule_render();
+                                }
+                                return;
+                            }
                             //region: send WsMessage over websocket
-                            game_data
-                                .ws
-                                .send_with_str(
-                                    &serde_json::to_string(&WsMessage::PlayerChange {
-                                        ws_client_instance: game_data.my_ws_client_instance,
-                                    })
-                                    .expect("error sending PlayerChange"),
-                                )
-                                .expect("Failed to send PlayerChange");
+                            let msg = WsMessage::PlayerChange {
+                                ws_client_instance: game_data.my_ws_client_instance,
+                            };
+                            if let Err(err) = game_data.send_game_message(msg) {
+                                game_data.show_error(err);
+                            }
                             //endregion
-                            root_rendering_component.take_turn(game_data);
+                            //game_data is moved and dropped inside take_turn
+                            if let Err(err) = root_rendering_component.take_turn(game_data, &vdom) {
+                                root_rendering_component.rc.borrow_mut().show_error(err);
+                            }
                             // Finally, re-render the component on the next animation frame.
                             vdom.schedule_render();
                         })
@@ -884,14 +2082,17 @@ bumpalo::format!(in bump, "{}",
                 if game_data.this_machine_player_number == game_data.player_turn {
                     h3(bump)
                         .attr("id", "ws_elem")
-                        .attr("style", "color:orange;")
-                        .children([text(
-                            bumpalo::format!(in bump, "Play !{}", "").into_bump_str(),
-                        )])
+                        .attr("style", indicator::style(&game_data, Status::Turn, bump))
+                        .children([text(indicator::label(
+                            &game_data,
+                            Status::Turn,
+                            "Play !",
+                            bump,
+                        ))])
                         .finish()
                 } else {
                     //return wait for the other player
-                    div_wait_for_other_player(bump)
+                    div_wait_for_other_player(&game_data, bump)
                 }
             } else {
                 //unpredictable situation
@@ -899,47 +2100,140 @@ bumpalo::format!(in bump, "{}",
                 h3(bump)
                     .attr("id", "ws_elem")
                     .children([text(
-                        bumpalo::format!(in bump, "gamestate: {} player {}", game_data.game_state.as_ref(),game_data.this_machine_player_number)
+                        bumpalo::format!(in bump, "gamestate: {:?} player {}", game_data.game_state,game_data.this_machine_player_number)
                             .into_bump_str(),
                     )])
                     .finish()
             }
         }
-        ///the text 'wait for other player' is used multiple times
-        fn div_wait_for_other_player(bump: &Bump) -> Node {
-            h3(bump)
-                .attr("id", "ws_elem")
-                .attr("style", "color:red;")
+        ///hot seat: between turns the player passes the device to the other player.
+        ///The other player clicks when he is ready and the cards are flipped back.
+        fn div_pass_the_device<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+            let next_player = if game_data.player_turn == 1 { 2 } else { 1 };
+            div(bump)
+                .attr("class", "pass_the_device")
+                .children([
+                    h3(bump)
+                        .attr("style", indicator::style(&game_data, Status::Notice, bump))
+                        .children([text(
+                            bumpalo::format!(in bump, "Pass the device to player{} !", next_player)
+                                .into_bump_str(),
+                        )])
+                        .finish(),
+                    h3(bump)
+                        .attr("id", "ws_elem")
+                        .attr("style", indicator::style(&game_data, Status::Action, bump))
+                        .children([text(
+                            bumpalo::format!(in bump, "player{}: click here when you are ready !", next_player)
+                                .into_bump_str(),
+                        )])
+                        .on("click", move |root, vdom, _event| {
+                            let root_rendering_component =
+                                root.unwrap_mut::<RootRenderingComponent>();
+                            let game_data = root_rendering_component.rc.borrow_mut();
+                            let result = root_rendering_component.take_turn(game_data, &vdom);
+                            //the device now belongs to the player whose turn it is
+                            let mut game_data = root_rendering_component.rc.borrow_mut();
+                            game_data.this_machine_player_number = game_data.player_turn;
+                            if let Err(err) = result {
+                                game_data.show_error(err);
+                            }
+                            vdom.schedule_render();
+                        })
+                        .finish(),
+                ])
+                .finish()
+        }
+        ///the settings on the table in the invitation
+        fn div_settings_proposal<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+            h4(bump)
                 .children([text(
-                    bumpalo::format!(in bump, "Wait for the other player.{}", "").into_bump_str(),
+                    bumpalo::format!(in bump, "Proposed settings: {}",
+                    settings::settings_text(&game_data.settings_proposal))
+                    .into_bump_str(),
                 )])
                 .finish()
         }
+        ///the start screen shows how the last invitation ended
+        fn div_invitation_outcome<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+            let notice = match game_data.invitation_outcome {
+                None => return div(bump).finish(),
+                Some(InvitationOutcome::Declined) => "The other player declined the invitation.",
+                Some(InvitationOutcome::Cancelled) => "The other player cancelled the invitation.",
+                Some(InvitationOutcome::Expired) => "The invitation expired without an answer.",
+            };
+            h4(bump)
+                .attr("style", indicator::style(&game_data, Status::Caution, bump))
+                .children([text(notice)])
+                .finish()
+        }
+        ///the text 'wait for other player' is used multiple times.
+        ///If the heartbeat says he is gone, there is no point in waiting.
+        fn div_wait_for_other_player<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+            let notice = match game_data.peer_presence() {
+                Some(PeerPresence::Gone) => "The other player is gone. Maybe he closed the tab.",
+                _ => "Wait for the other player.",
+            };
+            h3(bump)
+                .attr("id", "ws_elem")
+                .attr("style", indicator::style(&game_data, Status::Notice, bump))
+                .children([text(notice)])
+                .finish()
+        }
         //endregion
 
         //region: create the whole virtual dom. The verbose stuff is in private functions
         //this game_data mutable reference is dropped on the end of the function
         let game_data = self.rc.borrow();
+        //the css class of the colour scheme like "m_container high_contrast"
+        let container_class =
+            bumpalo::format!(in bump, "m_container {}", game_data.color_scheme.as_str())
+                .into_bump_str();
+
+        //the quiz has no grid
+        if let GameState::Quiz = game_data.game_state {
+            return div(bump)
+                .attr("class", container_class)
+                .children([
+                    quiz::div_quiz(&game_data, bump),
+                    audio::div_audio_settings(&game_data, bump),
+                    speech::div_speech_settings(&game_data, bump),
+                    theme::div_theme_settings(&game_data, bump),
+                    self.error_overlay.render(bump),
+                ])
+                .finish();
+        }
 
         div(bump)
-            .attr("class", "m_container")
+            .attr("class", container_class)
             .children([
                 div_grid_header(self, bump),
                 //div for the css grid object defined in css with <img> inside
                 div(bump)
                     .attr("class", "grid_container")
-                    .attr("style", "margin-left: auto;margin-right: auto;")
+                    .attr(
+                        "style",
+                        bumpalo::format!(in bump, "margin-left: auto;margin-right: auto;grid-template-columns: repeat({}, auto);", game_data.settings.grid_columns)
+                            .into_bump_str(),
+                    )
+                    .attr("role", "group")
+                    .attr("aria-label", "cards")
                     .children(div_grid_items(self, bump))
                     .finish(),
+                accessibility::div_live_region(&game_data, bump),
                 self.players_and_scores.render(bump),
                 div_game_status_and_player_actions(self, bump),
                 h5(bump)
                     .children([text(
-                        bumpalo::format!(in bump, "Count of Clicks: {}", game_data.count_all_clicks)
+                        bumpalo::format!(in bump, "Count of Clicks: {}{}", game_data.count_all_clicks, game_data.time_left_text())
                             .into_bump_str(),
                     )])
                     .finish(),
+                audio::div_audio_settings(&game_data, bump),
+                speech::div_speech_settings(&game_data, bump),
+                theme::div_theme_settings(&game_data, bump),
                 self.cached_rules_and_description.render(bump),
+                self.error_overlay.render(bump),
             ])
             .finish()
         //endregion
@@ -991,6 +2285,19 @@ impl Render for PlayersAndScores {
     {
         //this game_data mutable reference is dropped on the end of the function
         let game_data = self.rc.borrow();
+        //the turn is not only a colour: the other schemes add icons
+        let turn_status = |player_number: usize| {
+            if game_data.player_turn == player_number {
+                Status::Turn
+            } else {
+                Status::Waiting
+            }
+        };
+        let presence_status = match game_data.peer_presence() {
+            Some(PeerPresence::Online) => Status::Positive,
+            Some(PeerPresence::Lagging) => Status::Caution,
+            Some(PeerPresence::Gone) | None => Status::Negative,
+        };
         //return
         div(bump)
             .attr("class", "grid_container_players")
@@ -1004,36 +2311,59 @@ impl Render for PlayersAndScores {
                     .attr("class", "grid_item")
                     .attr(
                         "style",
-                        bumpalo::format!(in bump,"text-align: left;color:{};text-decoration:{}",
-                            if game_data.player_turn==1 {"green"} else {"red"},
+                        bumpalo::format!(in bump,"text-align: left;{}text-decoration:{}",
+                            indicator::css(&game_data, turn_status(1)),
                             if game_data.this_machine_player_number==1 {"underline"} else {"none"}
                         )
                         .into_bump_str(),
                     )
-                    .children([text(
-                        bumpalo::format!(in bump, "player1: {}",game_data.player1_points)
-                            .into_bump_str(),
-                    )])
+                    .children([text(indicator::label(
+                        &game_data,
+                        turn_status(1),
+                        &format!("player1: {}", game_data.player1_points),
+                        bump,
+                    ))])
                     .finish(),
                 div(bump)
                     .attr("class", "grid_item")
-                    .attr("style", "text-align: center;")
-                    .children([text("")])
+                    .attr(
+                        "style",
+                        bumpalo::format!(in bump, "text-align: center;{}",
+                            indicator::css(&game_data, presence_status))
+                        .into_bump_str(),
+                    )
+                    .children([text(if game_data.has_heartbeat() {
+                        indicator::label(
+                            &game_data,
+                            presence_status,
+                            &game_data.heartbeat.presence_text(js_sys::Date::now()),
+                            bump,
+                        )
+                    } else {
+                        ""
+                    })])
                     .finish(),
                 div(bump)
                     .attr("class", "grid_item")
                     .attr(
                         "style",
-                        bumpalo::format!(in bump,"text-align: right;color:{};text-decoration:{}",
-                            if game_data.player_turn==2 {"green"} else {"red"},
+                        bumpalo::format!(in bump,"text-align: right;{}text-decoration:{}",
+                            indicator::css(&game_data, turn_status(2)),
                             if game_data.this_machine_player_number==2 {"underline"} else {"none"}
                         )
                         .into_bump_str(),
                     )
-                    .children([text(
-                        bumpalo::format!(in bump, "player2: {}",game_data.player2_points)
-                            .into_bump_str(),
-                    )])
+                    .children([text(if game_data.is_solo() {
+                        //alone there is no player2
+                        ""
+                    } else {
+                        indicator::label(
+                            &game_data,
+                            turn_status(2),
+                            &format!("player2: {}", game_data.player2_points),
+                            bump,
+                        )
+                    })])
                     .finish(),
             ])
             .finish()
@@ -1041,47 +2371,123 @@ impl Render for PlayersAndScores {
 }
 //endregion
 
+//region: clock tick
+///the card face down shows its face until its flip down starts. Then the vdom renders the card back.
+///The clock tick is too slow for that, so it is a timeout only for this render.
+fn schedule_render_at_face_change(game_data: &GameData, vdom: &dodrio::VdomWeak) {
+    let delay_ms = match game_data
+        .card_animations
+        .ms_until_face_change(js_sys::Date::now())
+    {
+        Some(delay_ms) => delay_ms,
+        None => return,
+    };
+    let vdom = vdom.clone();
+    let cb_render = Closure::once_into_js(move || vdom.schedule_render());
+    let result = web_sys::window().map(|window| {
+        window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            cb_render.unchecked_ref(),
+            delay_ms.ceil() as i32,
+        )
+    });
+    if let Some(Err(err)) = result {
+        console::log_1(&format!("render timeout not set: {}", Mem2Error::js_message(&err)).into());
+    }
+}
+
+///the invitation timeout, the lobby presence, the time limit, the heartbeat, the reconnect with retransmit
+///and auto-flip need a tick every second
+fn setup_clock_tick(window: &web_sys::Window, vdom: &dodrio::Vdom) -> Mem2Result<()> {
+    let weak = vdom.weak();
+    let cb_tick: Closure<dyn Fn()> = Closure::wrap(Box::new(move || {
+        wasm_bindgen_futures::spawn_local(
+            weak.with_component({
+                let v2 = weak.clone();
+                move |root| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    //this game_data mutable reference is dropped on the end of the function
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    let is_invitation_changed = game_data.check_invitation_timeout();
+                    let is_lobby_changed = game_data.check_presence();
+                    let is_time_limit_changed = game_data.check_time_limit();
+                    let is_heartbeat_changed = game_data.check_heartbeat();
+                    game_data.check_connection();
+                    if is_invitation_changed
+                        || is_lobby_changed
+                        || is_time_limit_changed
+                        || is_heartbeat_changed
+                    {
+                        v2.schedule_render();
+                    }
+                    //auto-flip is the same as the click on 'take your turn'
+                    if game_data.is_auto_flip_due() && game_data.is_server_authoritative() {
+                        //only the server flips the cards back. The next view comes from it.
+                        game_data.second_click_time = js_sys::Date::now();
+                        let msg = WsMessage::AuthTakeTurn {
+                            ws_client_instance: game_data.my_ws_client_instance,
+                        };
+                        if let Err(err) = game_data.send_ws_message(&msg) {
+                            game_data.show_error(err);
+                        }
+                    } else if game_data.is_auto_flip_due() {
+                        let msg = WsMessage::PlayerChange {
+                            ws_client_instance: game_data.my_ws_client_instance,
+                        };
+                        if let Err(err) = game_data.send_game_message(msg) {
+                            game_data.show_error(err);
+                        }
+                        //game_data is moved and dropped inside take_turn
+                        if let Err(err) = root_rendering_component.take_turn(game_data, &v2) {
+                            root_rendering_component.rc.borrow_mut().show_error(err);
+                        }
+                        v2.schedule_render();
+                    }
+                }
+            })
+            .map_err(|_| ()),
+        );
+    }));
+    window
+        .set_interval_with_callback_and_timeout_and_arguments_0(
+            cb_tick.as_ref().unchecked_ref(),
+            1000,
+        )
+        .map_err(|err| Mem2Error::Timer(Mem2Error::js_message(&err)))?;
+    //the tick runs forever
+    cb_tick.forget();
+    Ok(())
+}
+//endregion
+
 //region: websocket communication
-///setup websocket connection
-fn setup_ws_connection(location_href: &str) -> WebSocket {
-    //web-sys has websocket for Rust exactly like javascript has¸
-    console::log_1(&"location_href".into());
-    console::log_1(&wasm_bindgen::JsValue::from_str(location_href));
-    //location_href comes in this format  http://localhost:4000/
-    let mut loc_href = location_href
-        .replace("http://", "ws://")
-        .replace("https://", "wss://");
-    //Only for debugging in the development environment
-    //let mut loc_href = String::from("ws://192.168.1.57:80/");
-    loc_href.push_str("mem2ws/");
-    console::log_1(&wasm_bindgen::JsValue::from_str(&loc_href));
-    //same server address and port as http server
-    let ws = WebSocket::new(&loc_href).expect("WebSocket failed to connect.");
+///setup the connection of the transport chosen at startup
+fn setup_transport_connection(kind: &TransportKind, ws_url: &str) -> Mem2Result<Rc<dyn Transport>> {
+    let transport = transport::new_transport(kind, ws_url)?;
 
     //I don't know why is clone needed
-    let ws_c = ws.clone();
+    let transport_c = Rc::clone(&transport);
     //It looks that the first send is in some way a handshake and is part of the connection
     //it will be execute onopen as a closure
-    let open_handler = Box::new(move || {
+    transport.set_on_open(Box::new(move || {
         console::log_1(&"Connection opened, sending 'test' to server".into());
-        ws_c.send_with_str(
-            &serde_json::to_string(&WsMessage::ConnectionTest {
-                test: String::from("test"),
-            })
-            .expect("error sending test"),
-        )
-        .expect("Failed to send 'test' to server");
-    });
-
-    let cb_oh: Closure<dyn Fn()> = Closure::wrap(open_handler);
-    ws.set_onopen(Some(cb_oh.as_ref().unchecked_ref()));
-    //don't drop the open_handler memory
-    cb_oh.forget();
-    ws
+        //the test is only a handshake. The real messages show their errors in the overlay.
+        let result = serde_json::to_string(&WsMessage::ConnectionTest {
+            test: String::from("test"),
+        })
+        .map_err(Mem2Error::from)
+        .and_then(|json| transport_c.send(&json));
+        if let Err(err) = result {
+            console::log_1(&format!("error: {}", err).into());
+        }
+    }));
+    transport.set_on_close(Box::new(|| {
+        console::log_1(&"Connection closed.".into());
+    }));
+    Ok(transport)
 }
 
 /// receive websocket msg callback. I don't understand this much. Too much future and promises.
-fn setup_ws_msg_recv(ws: &WebSocket, vdom: &dodrio::Vdom) {
+fn setup_transport_msg_recv(transport: &dyn Transport, room_key: RoomKey, vdom: &dodrio::Vdom) {
     //Player1 on machine1 have a button Ask player to play! before he starts to play.
     //Click and it sends the WsMessage want_to_play. Player1 waits for the reply and cannot play.
     //Player2 on machine2 see the WsMessage and Accepts it.
@@ -1092,130 +2498,630 @@ fn setup_ws_msg_recv(ws: &WebSocket, vdom: &dodrio::Vdom) {
     //The method with_component() needs a future (promise) It will be executed on the next vdom tick.
     //This is the only way I found to write to CardGrid fields.
     let weak = vdom.weak();
-    let msg_recv_handler = Box::new(move |msg: JsValue| {
-        let data: JsValue =
-            Reflect::get(&msg, &"data".into()).expect("No 'data' field in websocket message!");
-
+    let is_server_connection = transport.is_server_connection();
+    transport.set_on_message(Box::new(move |frame: Frame| {
         //serde_json can find out the variant of WsMessage
-        //parse json and put data in the enum
-        let msg: WsMessage =
-            serde_json::from_str(&data.as_string().expect("Field 'data' is not string"))
-                .unwrap_or_else(|_x| WsMessage::ConnectionTest {
-                    test: String::from("error"),
-                });
-
-        //match enum by variant and prepares the future that will be executed on the next tick
-        match msg {
-            WsMessage::ConnectionTest { test } => console::log_1(&test.into()),
-            WsMessage::WantToPlay { ws_client_instance } => {
-                wasm_bindgen_futures::spawn_local(
-                    weak.with_component({
-                        let v2 = weak.clone();
-                        move |root| {
-                            let root_rendering_component =
-                                root.unwrap_mut::<RootRenderingComponent>();
-                            //this game_data mutable reference is dropped on the end of the function
-                            let mut game_data = root_rendering_component.rc.borrow_mut();
-                            if let GameState::Start = game_data.game_state {
-                                console::log_1(&"rcv wanttoplay".into());
-                                game_data.game_state = GameState::Asked;
-                                game_data.other_ws_client_instance = ws_client_instance;
-                                v2.schedule_render();
-                            }
-                        }
-                    })
-                    .map_err(|_| ()),
-                );
+        //parse json or binary and put data in the enum
+        let msg: Mem2Result<WsMessage> = match frame {
+            Frame::Text(data) => serde_json::from_str(&data).map_err(Mem2Error::from),
+            Frame::Binary(bytes) => WsMessage::from_binary(&bytes).map_err(Mem2Error::from),
+        };
+        let msg: WsMessage = msg.unwrap_or_else(|_x| {
+            console::log_1(&"rejected message: it is not a valid WsMessage".into());
+            WsMessage::ConnectionTest {
+                test: String::from("error"),
             }
-            WsMessage::AcceptPlay {
-                ws_client_instance,
-                card_grid_data,
-            } => {
-                wasm_bindgen_futures::spawn_local(
-                    weak.with_component({
-                        let v2 = weak.clone();
-                        move |root| {
-                            console::log_1(&"rcv AcceptPlay".into());
-                            let root_rendering_component =
-                                root.unwrap_mut::<RootRenderingComponent>();
-                            //this game_data mutable reference is dropped on the end of the function
-                            let mut game_data = root_rendering_component.rc.borrow_mut();
+        });
+        //the lobby and the invitation come only from the players with the same invite link
+        let msg = match msg {
+            WsMessage::RoomSealed { msg, mac } => match room_key.open(*msg, &mac) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    console::log_1(&format!("dropped message: {}", err).into());
+                    return;
+                }
+            },
+            msg if msg.needs_room_seal() => {
+                console::log_1(&"dropped message: it is not sealed with the room key".into());
+                return;
+            }
+            msg => msg,
+        };
+        //the server-authoritative game comes only from the server. Another tab could forge it.
+        if msg.is_server_only() && !is_server_connection {
+            console::log_1(&"dropped message: a server message not from the server".into());
+            return;
+        }
+        receive_ws_message(msg, false, &weak);
+    }));
+}
 
-                            game_data.player_turn = 1;
-                            game_data.game_state = GameState::Play;
-                            let v: Vec<Card> = serde_json::from_str(card_grid_data.as_str())
-                                .expect("Field 'text' is not Vec<Card>");
-                            game_data.vec_cards = v;
+///match enum by variant and prepares the future that will be executed on the next tick.
+///The game messages from the other player are accepted only sealed, if the game has a session key.
+///The server messages are accepted only directly from the server, never inside a message of a player.
+fn receive_ws_message(msg: WsMessage, is_sealed: bool, weak: &dodrio::VdomWeak) {
+    if is_sealed && msg.is_server_only() {
+        console::log_1(&"dropped message: a server message from a player".into());
+        return;
+    }
+    match msg {
+        WsMessage::ConnectionTest { test } => console::log_1(&test.into()),
+        WsMessage::WantToPlay {
+            ws_client_instance,
+            other_ws_client_instance,
+            settings,
+            wire_format,
+            nonce,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //the challenge is addressed only to me
+                        if other_ws_client_instance == game_data.my_ws_client_instance
+                            && game_data.accepts_received(GameEvent::WantToPlayReceived)
+                        {
+                            console::log_1(&"rcv wanttoplay".into());
+                            //the proposed settings must be playable
+                            if let Err(err) = settings.check() {
+                                game_data.reject_message(ws_client_instance, &err);
+                                return;
+                            }
+                            if let Err(err) = game_data.fire(GameEvent::WantToPlayReceived) {
+                                game_data.show_error(err);
+                            }
                             game_data.other_ws_client_instance = ws_client_instance;
+                            game_data.settings_proposal = settings;
+                            game_data.wire_format_proposal = wire_format;
+                            game_data.nonce_proposal = nonce;
                             v2.schedule_render();
-                            //}
                         }
-                    })
-                    .map_err(|_| ()),
-                );
-            }
-            WsMessage::PlayerClick {
-                ws_client_instance,
-                card_index,
-                count_click_inside_one_turn,
-            } => {
-                wasm_bindgen_futures::spawn_local(
-                    weak.with_component({
-                        let v2 = weak.clone();
-                        console::log_1(&"player_click".into());
-                        move |root| {
-                            let root_rendering_component =
-                                root.unwrap_mut::<RootRenderingComponent>();
-                            //this game_data mutable reference is dropped on the end of the function
-                            let mut game_data = root_rendering_component.rc.borrow_mut();
-                            //rcv only from one other player
-                            if ws_client_instance == game_data.other_ws_client_instance {
-                                console::log_1(&"other_ws_client_instance".into());
-                                game_data.count_click_inside_one_turn = count_click_inside_one_turn;
-                                if count_click_inside_one_turn == 1 {
-                                    game_data.card_index_of_first_click = card_index;
-                                } else if count_click_inside_one_turn == 2 {
-                                    game_data.card_index_of_second_click = card_index;
-                                } else {
-                                    //nothing
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::CounterPropose {
+            ws_client_instance,
+            other_ws_client_instance,
+            settings,
+            wire_format,
+            nonce,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //only the answer of the player I asked
+                        if other_ws_client_instance == game_data.my_ws_client_instance
+                            && ws_client_instance == game_data.other_ws_client_instance
+                            && game_data.accepts_received(GameEvent::CounterProposalReceived)
+                        {
+                            console::log_1(&"rcv CounterPropose".into());
+                            if let Err(err) = settings.check() {
+                                game_data.reject_message(ws_client_instance, &err);
+                                return;
+                            }
+                            //now I must answer like the asked player
+                            if let Err(err) = game_data.fire(GameEvent::CounterProposalReceived) {
+                                game_data.show_error(err);
+                            }
+                            game_data.settings_proposal = settings;
+                            game_data.wire_format_proposal = wire_format;
+                            game_data.nonce_proposal = nonce;
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::AcceptPlay {
+            ws_client_instance,
+            vec_cards,
+            settings,
+            wire_format,
+            nonce,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        console::log_1(&"rcv AcceptPlay".into());
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //only the player that asked waits for the accept of the challenged player
+                        if ws_client_instance != game_data.other_ws_client_instance
+                            || !game_data.accepts_received(GameEvent::AcceptPlayReceived)
+                        {
+                            return;
+                        }
+                        //the accepted settings must be the proposed settings
+                        if settings != game_data.settings_proposal {
+                            game_data
+                                .reject_message(ws_client_instance, &EngineError::InvalidSettings);
+                            return;
+                        }
+                        //the cards come from the other player and must be a valid deck
+                        if let Err(err) = mem2_engine::check_deck(&vec_cards, settings.card_pairs())
+                        {
+                            game_data.reject_message(ws_client_instance, &err);
+                            return;
+                        }
+                        game_data.settings = settings;
+                        game_data.vec_cards = vec_cards;
+                        game_data.preload_sounds();
+                        //binary only if I proposed binary too
+                        game_data.wire_format = game_data.my_wire_format.agree(wire_format);
+                        //my proposal was accepted, so I am player 1
+                        let key = SessionKey::derive(
+                            &game_data.room_secret,
+                            (game_data.my_ws_client_instance, game_data.my_nonce),
+                            (ws_client_instance, nonce),
+                        );
+                        game_data.peer_session = Some(PeerSession::new(
+                            key,
+                            game_data.my_ws_client_instance,
+                            ws_client_instance,
+                        ));
+                        if let Err(err) = game_data.fire(GameEvent::AcceptPlayReceived) {
+                            game_data.show_error(err);
+                        }
+                        game_data.other_ws_client_instance = ws_client_instance;
+                        v2.schedule_render();
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::PlayerClick {
+            ws_client_instance,
+            card_index,
+            count_click_inside_one_turn,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    console::log_1(&"player_click".into());
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //rcv only from one other player
+                        if ws_client_instance == game_data.other_ws_client_instance
+                            && game_data.accepts_sealed(is_sealed)
+                        {
+                            console::log_1(&"other_ws_client_instance".into());
+                            if let Err(err) = game_data
+                                .validate_player_click(card_index, count_click_inside_one_turn)
+                            {
+                                game_data.reject_message(ws_client_instance, &err);
+                                return;
+                            }
+                            game_data.count_click_inside_one_turn = count_click_inside_one_turn;
+                            if count_click_inside_one_turn == 1 {
+                                game_data.card_index_of_first_click = card_index;
+                            } else if count_click_inside_one_turn == 2 {
+                                game_data.card_index_of_second_click = card_index;
+                            } else {
+                                //nothing
+                            }
+                            //game_data is moved and dropped inside card_on_click
+                            if let Err(err) = root_rendering_component.card_on_click(game_data) {
+                                root_rendering_component.rc.borrow_mut().show_error(err);
+                            }
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::PlayerChange { ws_client_instance } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //rcv only from other player
+                        if ws_client_instance == game_data.other_ws_client_instance
+                            && game_data.accepts_sealed(is_sealed)
+                        {
+                            console::log_1(&"PlayerChange".into());
+                            if let Err(err) = game_data.validate_player_change() {
+                                game_data.reject_message(ws_client_instance, &err);
+                                return;
+                            }
+                            //game_data is moved and dropped inside take_turn
+                            if let Err(err) = root_rendering_component.take_turn(game_data, &v2) {
+                                root_rendering_component.rc.borrow_mut().show_error(err);
+                            }
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::Presence {
+            ws_client_instance,
+            player_name,
+            status,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //not the echo of myself
+                        if ws_client_instance != game_data.my_ws_client_instance {
+                            game_data.lobby.update(
+                                ws_client_instance,
+                                &player_name,
+                                status,
+                                js_sys::Date::now(),
+                            );
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::Ping {
+            ws_client_instance,
+            other_ws_client_instance,
+            sent_at,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //only the ping of the other player in my game. The answer returns his time.
+                        if other_ws_client_instance == game_data.my_ws_client_instance
+                            && ws_client_instance == game_data.other_ws_client_instance
+                            && game_data.has_heartbeat()
+                            && game_data.accepts_sealed(is_sealed)
+                        {
+                            game_data.heartbeat.on_ping(js_sys::Date::now());
+                            let msg = WsMessage::Pong {
+                                ws_client_instance: game_data.my_ws_client_instance,
+                                other_ws_client_instance: ws_client_instance,
+                                sent_at,
+                            };
+                            if let Err(err) = game_data.send_heartbeat(msg) {
+                                console::log_1(&format!("pong not sent: {}", err).into());
+                            }
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::Pong {
+            ws_client_instance,
+            other_ws_client_instance,
+            sent_at,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //only the answer to my ping
+                        if other_ws_client_instance == game_data.my_ws_client_instance
+                            && ws_client_instance == game_data.other_ws_client_instance
+                            && game_data.has_heartbeat()
+                            && game_data.accepts_sealed(is_sealed)
+                        {
+                            game_data.heartbeat.on_pong(sent_at, js_sys::Date::now());
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::Sealed {
+            ws_client_instance,
+            other_ws_client_instance,
+            sequence,
+            msg,
+            mac,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //the sealed messages of other games are not for me
+                        if other_ws_client_instance != game_data.my_ws_client_instance {
+                            return;
+                        }
+                        let opened = game_data.peer_session.as_mut().map(|peer_session| {
+                            peer_session.open(
+                                ws_client_instance,
+                                other_ws_client_instance,
+                                sequence,
+                                *msg,
+                                &mac,
+                            )
+                        });
+                        match opened {
+                            //the messages inside are handled on the next tick in order
+                            Some(Ok(vec_msg)) => {
+                                game_data.send_ack();
+                                for msg in vec_msg {
+                                    receive_ws_message(msg, true, &v2);
                                 }
-                                root_rendering_component.card_on_click(game_data);
-                                v2.schedule_render();
                             }
+                            //the other player sends again, because he did not get my ack
+                            Some(Err(EngineError::ReplayedMessage)) => game_data.send_ack(),
+                            Some(Err(err)) => {
+                                console::log_1(&format!("dropped message: {}", err).into())
+                            }
+                            None => console::log_1(&"dropped message: no game session".into()),
                         }
-                    })
-                    .map_err(|_| ()),
-                );
-            }
-            WsMessage::PlayerChange { ws_client_instance } => {
-                wasm_bindgen_futures::spawn_local(
-                    weak.with_component({
-                        let v2 = weak.clone();
-                        move |root| {
-                            let root_rendering_component =
-                                root.unwrap_mut::<RootRenderingComponent>();
-                            //this game_data mutable reference is dropped on the end of the function
-                            //clippy is wrong about dropping the mut. I need it.
-                            let game_data = root_rendering_component.rc.borrow_mut();
-                            //rcv only from other player
-                            if ws_client_instance == game_data.other_ws_client_instance {
-                                console::log_1(&"PlayerChange".into());
-                                root_rendering_component.take_turn(game_data);
-                                v2.schedule_render();
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::SealedHeartbeat {
+            ws_client_instance,
+            other_ws_client_instance,
+            sequence,
+            msg,
+            mac,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //the heartbeats of other games are not for me
+                        if other_ws_client_instance != game_data.my_ws_client_instance {
+                            return;
+                        }
+                        let opened = game_data.peer_session.as_mut().map(|peer_session| {
+                            peer_session.open_heartbeat(
+                                ws_client_instance,
+                                other_ws_client_instance,
+                                sequence,
+                                *msg,
+                                &mac,
+                            )
+                        });
+                        //no ack, a lost heartbeat is not sent again
+                        match opened {
+                            Some(Ok(msg)) => receive_ws_message(msg, true, &v2),
+                            Some(Err(err)) => {
+                                console::log_1(&format!("dropped heartbeat: {}", err).into())
                             }
+                            None => console::log_1(&"dropped heartbeat: no game session".into()),
                         }
-                    })
-                    .map_err(|_| ()),
-                );
-            }
+                    }
+                })
+                .map_err(|_| ()),
+            );
         }
-    });
-
-    //magic ??
-    let cb_mrh: Closure<dyn Fn(JsValue)> = Closure::wrap(msg_recv_handler);
-    ws.set_onmessage(Some(cb_mrh.as_ref().unchecked_ref()));
-
-    //don't drop the eventlistener from memory
-    cb_mrh.forget();
+        WsMessage::Ack {
+            ws_client_instance,
+            other_ws_client_instance,
+            sequence,
+            mac,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component(move |root| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    //this game_data mutable reference is dropped on the end of the function
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    //the acks of other games are not for me
+                    if other_ws_client_instance != game_data.my_ws_client_instance {
+                        return;
+                    }
+                    if let Some(peer_session) = game_data.peer_session.as_mut() {
+                        if let Err(err) = peer_session.on_ack(
+                            ws_client_instance,
+                            other_ws_client_instance,
+                            sequence,
+                            &mac,
+                        ) {
+                            console::log_1(&format!("dropped ack: {}", err).into());
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::CancelInvite { ws_client_instance } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //only the invitation I was asked
+                        if ws_client_instance == game_data.other_ws_client_instance
+                            && game_data.accepts_received(GameEvent::CancelInviteReceived)
+                        {
+                            console::log_1(&"rcv CancelInvite".into());
+                            game_data.end_invitation(
+                                GameEvent::CancelInviteReceived,
+                                Some(InvitationOutcome::Cancelled),
+                            );
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::DeclinePlay {
+            ws_client_instance,
+            other_ws_client_instance,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //only the answer to my invitation from the player I invited
+                        if other_ws_client_instance == game_data.my_ws_client_instance
+                            && ws_client_instance == game_data.other_ws_client_instance
+                            && game_data.accepts_received(GameEvent::DeclinePlayReceived)
+                        {
+                            console::log_1(
+                                &format!("rcv DeclinePlay from {}", ws_client_instance).into(),
+                            );
+                            game_data.end_invitation(
+                                GameEvent::DeclinePlayReceived,
+                                Some(InvitationOutcome::Declined),
+                            );
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        //the room seal is opened when the message is received. Inside another message it is not valid.
+        WsMessage::RoomSealed { .. } => {
+            console::log_1(&"dropped message: a room sealed message inside another message".into());
+        }
+        WsMessage::ProtocolError {
+            ws_client_instance,
+            other_ws_client_instance,
+            reason,
+        } => {
+            console::log_1(
+                &format!(
+                    "protocol error from {} for {}: {}",
+                    ws_client_instance, other_ws_client_instance, reason
+                )
+                .into(),
+            );
+        }
+        WsMessage::AuthStart {
+            ws_client_instance,
+            other_ws_client_instance,
+            player_number,
+            settings,
+            game_view,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //the server sends it only to me, but the relay could be shared
+                        if ws_client_instance == game_data.my_ws_client_instance
+                            && game_data.accepts_received(GameEvent::ServerStarted)
+                        {
+                            console::log_1(&"rcv AuthStart".into());
+                            //the settings of the player that waited, checked like in the invitation
+                            if let Err(err) = settings.check() {
+                                game_data.show_error(err.into());
+                                return;
+                            }
+                            game_data.other_ws_client_instance = other_ws_client_instance;
+                            game_data.this_machine_player_number = player_number;
+                            game_data.apply_settings(settings);
+                            if let Err(err) = game_data.fire(GameEvent::ServerStarted) {
+                                game_data.show_error(err);
+                            }
+                            game_data.apply_game_view(game_view);
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::AuthView {
+            vec_ws_client_instance,
+            game_view,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        //only in my server game
+                        if vec_ws_client_instance.contains(&game_data.my_ws_client_instance)
+                            && game_data.is_server_authoritative()
+                            && game_data.game_state == GameState::Play
+                        {
+                            game_data.apply_game_view(game_view);
+                            schedule_render_at_face_change(&game_data, &v2);
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        WsMessage::AuthError {
+            ws_client_instance,
+            reason,
+            is_game_over,
+        } => {
+            wasm_bindgen_futures::spawn_local(
+                weak.with_component({
+                    let v2 = weak.clone();
+                    move |root| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        //this game_data mutable reference is dropped on the end of the function
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        if ws_client_instance == game_data.my_ws_client_instance
+                            && game_data.is_server_authoritative()
+                        {
+                            game_data.show_error(Mem2Error::Server(reason));
+                            //the other player left: the game cannot go on
+                            if is_game_over && game_data.accepts_received(GameEvent::ServerEnded) {
+                                if let Err(err) = game_data.fire(GameEvent::ServerEnded) {
+                                    game_data.show_error(err);
+                                }
+                            }
+                            v2.schedule_render();
+                        }
+                    }
+                })
+                .map_err(|_| ()),
+            );
+        }
+        //only the server receives the intents
+        WsMessage::AuthJoin { .. }
+        | WsMessage::AuthFlip { .. }
+        | WsMessage::AuthTakeTurn { .. } => {}
+    }
 }
 //endregion
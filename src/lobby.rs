@@ -0,0 +1,149 @@
+//! lobby.rs - the players online on the start screen.
+//! The list itself is `mem2_engine::Lobby`, built from the `Presence` messages.
+//! The player clicks an idle player to challenge him. The `WantToPlay` is addressed only to that client.
+//! The lobby shows only the players that opened the same invite link, because the messages are sealed with the room key.
+
+use crate::indicator::{self, Status};
+use crate::{local_storage, GameData, RootRenderingComponent};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use mem2_engine::{GameEvent, PresenceStatus, WsMessage, PLAYER_NAME_MAX_CHARS};
+use web_sys::console;
+
+///the name of the item in localStorage
+const PLAYER_NAME_STORAGE_KEY: &str = "mem2_player_name";
+
+///read the player name from localStorage. The default name has the ws client instance.
+pub fn load_player_name(my_ws_client_instance: usize) -> String {
+    local_storage()
+        .and_then(|storage| storage.get_item(PLAYER_NAME_STORAGE_KEY).ok())
+        .and_then(|opt| opt)
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| format!("Player{}", my_ws_client_instance))
+}
+
+///write the player name to localStorage
+fn save_player_name(player_name: &str) {
+    if let Some(storage) = local_storage() {
+        if storage
+            .set_item(PLAYER_NAME_STORAGE_KEY, player_name)
+            .is_err()
+        {
+            console::log_1(&"error: cannot save the player name to localStorage".into());
+        }
+    }
+}
+
+///the lobby on the start screen: my name and the players online
+pub fn div_lobby<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    let mut vec_rows = Vec::new();
+    vec_rows.push(
+        h4(bump)
+            .children([text(
+                bumpalo::format!(in bump, "Your name: {} (click to change)", game_data.player_name)
+                    .into_bump_str(),
+            )])
+            .on("click", move |root, vdom, _event| {
+                let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                let mut game_data = root_rendering_component.rc.borrow_mut();
+                let window = match web_sys::window() {
+                    Some(window) => window,
+                    None => return,
+                };
+                if let Ok(Some(player_name)) =
+                    window.prompt_with_message_and_default("Your name:", &game_data.player_name)
+                {
+                    let player_name: String = player_name
+                        .trim()
+                        .chars()
+                        .take(PLAYER_NAME_MAX_CHARS)
+                        .collect();
+                    if !player_name.is_empty() {
+                        save_player_name(&player_name);
+                        game_data.player_name = player_name;
+                        game_data.announce_presence();
+                        vdom.schedule_render();
+                    }
+                }
+            })
+            .finish(),
+    );
+    //only the players with this link are in my lobby
+    vec_rows.push(
+        h4(bump)
+            .children([text(
+                bumpalo::format!(in bump, "Invite link: {}", game_data.invite_link).into_bump_str(),
+            )])
+            .finish(),
+    );
+    if game_data.lobby.vec_players.is_empty() {
+        vec_rows.push(
+            h4(bump)
+                .children([text("Nobody else is online yet.")])
+                .finish(),
+        );
+    } else {
+        vec_rows.push(
+            h4(bump)
+                .children([text("Click an idle player to challenge:")])
+                .finish(),
+        );
+    }
+    for player in &game_data.lobby.vec_players {
+        let ws_client_instance = player.ws_client_instance;
+        let (label, status) = match player.status {
+            PresenceStatus::Idle => ("idle", Status::Positive),
+            PresenceStatus::InGame => ("in game", Status::Negative),
+        };
+        let mut row = div(bump)
+            .attr("class", "grid_container_players")
+            .attr(
+                "style",
+                bumpalo::format!(in bump, "grid-template-columns: auto auto;{}",
+                    indicator::css(game_data, status))
+                .into_bump_str(),
+            )
+            .children([
+                div(bump)
+                    .attr("class", "grid_item")
+                    .children([text(
+                        bumpalo::format!(in bump, "{}", player.player_name).into_bump_str(),
+                    )])
+                    .finish(),
+                div(bump)
+                    .attr("class", "grid_item")
+                    .children([text(indicator::label(game_data, status, label, bump))])
+                    .finish(),
+            ]);
+        if let PresenceStatus::Idle = player.status {
+            row = row.on("click", move |root, vdom, _event| {
+                let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                let mut game_data = root_rendering_component.rc.borrow_mut();
+                if let Err(err) = game_data.fire(GameEvent::AskToPlay) {
+                    game_data.show_error(err);
+                    vdom.schedule_render();
+                    return;
+                }
+                //the challenge is only for this player, with my settings
+                game_data.this_machine_player_number = 1;
+                game_data.other_ws_client_instance = ws_client_instance;
+                game_data.settings_proposal = game_data.my_settings.clone();
+                game_data.my_nonce = crate::new_nonce();
+                let msg = WsMessage::WantToPlay {
+                    ws_client_instance: game_data.my_ws_client_instance,
+                    other_ws_client_instance: ws_client_instance,
+                    settings: game_data.settings_proposal.clone(),
+                    wire_format: game_data.my_wire_format,
+                    nonce: game_data.my_nonce,
+                };
+                if let Err(err) = game_data.send_ws_message(&msg) {
+                    game_data.show_error(err);
+                }
+                vdom.schedule_render();
+            });
+        }
+        vec_rows.push(row.finish());
+    }
+    div(bump).children(vec_rows).finish()
+}
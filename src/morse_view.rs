@@ -0,0 +1,45 @@
+//! morse_view.rs - the dots and dashes of the flipped card under its spelling word.
+//! Kids learn to read the Morse code as well as to hear it. The code comes from the same Morse table as the sound.
+//! While the synthesized sound plays, every symbol lights up with its tone. It is a CSS animation with the
+//! delay and duration of the tone, so no render loop is needed.
+
+use crate::tone::LEAD_IN_S;
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use mem2_engine::morse;
+
+///the dots and dashes of the letter. With the Morse speed they light up in sync with the sound.
+pub fn div_morse_code<'a>(
+    letter: Option<char>,
+    animation_wpm: Option<u32>,
+    bump: &'a Bump,
+) -> Node<'a> {
+    let mut vec_symbols = Vec::new();
+    if let Some(letter) = letter {
+        for tone in morse::tones(&letter.to_string(), animation_wpm.unwrap_or(1)) {
+            let symbol = if tone.symbol == '-' {
+                "\u{2014}"
+            } else {
+                "\u{2022}"
+            };
+            let span_symbol = match animation_wpm {
+                Some(_) => span(bump)
+                    .attr("class", "morse_symbol morse_symbol_animated")
+                    .attr(
+                        "style",
+                        bumpalo::format!(in bump, "animation-delay: {:.0}ms; animation-duration: {:.0}ms;",
+                            LEAD_IN_S * 1000.0 + tone.start_ms,
+                            tone.duration_ms)
+                        .into_bump_str(),
+                    ),
+                None => span(bump).attr("class", "morse_symbol"),
+            };
+            vec_symbols.push(span_symbol.children([text(symbol)]).finish());
+        }
+    }
+    div(bump)
+        .attr("class", "morse_code")
+        .children(vec_symbols)
+        .finish()
+}
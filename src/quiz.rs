@@ -0,0 +1,157 @@
+//! quiz.rs - the quiz mini-game: which letter is this?
+//! The quiz shows the image of a card or plays its Morse code. The child picks the letter or the spelling word.
+//! It uses the images of the chosen deck and the spelling from text.json. The rules are in `mem2_engine`.
+
+use crate::indicator::{self, Status};
+use crate::{get_spelling, GameData, RootRenderingComponent};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use mem2_engine::{morse, GameEvent, QuizAnswerKind, QuizPrompt, STREAK_POINTS_MAX};
+
+///the text of the choice. Until the spelling is loaded the words are letters.
+fn choice_label(answer_kind: QuizAnswerKind, card_number: usize) -> String {
+    let letter = morse::card_letter(card_number).unwrap_or(' ');
+    match answer_kind {
+        QuizAnswerKind::Letter => letter.to_string(),
+        QuizAnswerKind::Word => get_spelling()
+            .ok()
+            .and_then(|spelling| spelling.name.get(card_number).cloned())
+            .unwrap_or_else(|| letter.to_string()),
+    }
+}
+
+///the score, the question, the choices and after the answer the next question
+pub fn div_quiz<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    let quiz = &game_data.quiz;
+    let mut vec_children = vec![h3(bump)
+        .attr("style", indicator::style(game_data, Status::Positive, bump))
+        .children([text(
+            bumpalo::format!(in bump, "Quiz score: {} streak: {} best: {}",
+                quiz.score,
+                quiz.streak,
+                quiz.best_streak)
+            .into_bump_str(),
+        )])
+        .finish()];
+
+    if let Some(question) = &quiz.question {
+        //region: the prompt
+        match question.prompt {
+            QuizPrompt::Image => vec_children.push(
+                img(bump)
+                    .attr(
+                        "src",
+                        bumpalo::format!(in bump, "{}/img/mem_image_{:02}.png",
+                            game_data.settings.deck,
+                            question.card_number)
+                        .into_bump_str(),
+                    )
+                    .attr("style", "max-width: 200px;")
+                    .finish(),
+            ),
+            QuizPrompt::Morse => vec_children.push(
+                h3(bump)
+                    .attr("style", indicator::style(game_data, Status::Action, bump))
+                    .children([text("Listen to the Morse code! Click to hear it again.")])
+                    .on("click", move |root, vdom, _event| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        if let Err(err) = game_data.play_quiz_sound() {
+                            game_data.show_error(err);
+                        }
+                        vdom.schedule_render();
+                    })
+                    .finish(),
+            ),
+        }
+        //endregion
+
+        //region: the choices. After the answer the right one is green and the wrong pick red.
+        let mut vec_choices = Vec::new();
+        for card_number in question.vec_choices.iter().copied() {
+            let status = match question.chosen {
+                Some(_) if card_number == question.card_number => Status::Positive,
+                Some(chosen) if chosen == card_number => Status::Negative,
+                _ => Status::Action,
+            };
+            vec_choices.push(
+                div(bump)
+                    .attr("class", "grid_item")
+                    .attr("style", indicator::style(game_data, status, bump))
+                    .children([text(indicator::label(
+                        game_data,
+                        status,
+                        &choice_label(question.answer_kind, card_number),
+                        bump,
+                    ))])
+                    .on("click", move |root, vdom, _event| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        root_rendering_component
+                            .rc
+                            .borrow_mut()
+                            .quiz
+                            .answer(card_number);
+                        vdom.schedule_render();
+                    })
+                    .finish(),
+            );
+        }
+        vec_children.push(
+            div(bump)
+                .attr("class", "grid_container_header")
+                .attr("style", "grid-template-columns: auto auto;")
+                .children(vec_choices)
+                .finish(),
+        );
+        //endregion
+
+        if let Some(is_correct) = question.is_correct() {
+            let status = if is_correct {
+                Status::Positive
+            } else {
+                Status::Negative
+            };
+            let feedback = if is_correct {
+                bumpalo::format!(in bump, "Right! +{} points", quiz.streak.min(STREAK_POINTS_MAX))
+            } else {
+                bumpalo::format!(in bump, "It was {}.",
+                    choice_label(question.answer_kind, question.card_number))
+            };
+            vec_children.push(
+                h3(bump)
+                    .attr("style", indicator::style(game_data, status, bump))
+                    .children([text(indicator::label(game_data, status, &feedback, bump))])
+                    .finish(),
+            );
+            vec_children.push(
+                h3(bump)
+                    .attr("style", indicator::style(game_data, Status::Action, bump))
+                    .children([text("Next question!")])
+                    .on("click", move |root, vdom, _event| {
+                        let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                        let mut game_data = root_rendering_component.rc.borrow_mut();
+                        game_data.next_quiz_question();
+                        vdom.schedule_render();
+                    })
+                    .finish(),
+            );
+        }
+    }
+
+    vec_children.push(
+        h3(bump)
+            .attr("style", indicator::style(game_data, Status::Negative, bump))
+            .children([text("Quit the quiz")])
+            .on("click", move |root, vdom, _event| {
+                let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                let mut game_data = root_rendering_component.rc.borrow_mut();
+                if let Err(err) = game_data.fire(GameEvent::QuitQuiz) {
+                    game_data.show_error(err);
+                }
+                vdom.schedule_render();
+            })
+            .finish(),
+    );
+    div(bump).children(vec_children).finish()
+}
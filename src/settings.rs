@@ -0,0 +1,137 @@
+//! settings.rs - the game settings on the screen.
+//! On the start screen the player chooses the settings he will propose in the invitation.
+//! Every click on a setting changes it to the next allowed option from mem2_engine.
+//! The asked player sees the proposed settings and can accept them or counter-propose his own.
+
+use crate::indicator::{self, Status};
+use crate::{GameData, RootRenderingComponent};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use mem2_engine::{GameSettings, MatchRule, DECKS, GRID_SIZES, TIME_LIMITS_S};
+
+///the next option in the list after the current one. After the last comes the first.
+fn next_option<T: PartialEq + Clone>(options: &[T], current: &T) -> T {
+    let position = options.iter().position(|option| option == current);
+    let next = position.map_or(0, |position| (position + 1) % options.len());
+    options.get(next).unwrap_or(current).clone()
+}
+
+///short text of the time limit
+fn time_limit_text(time_limit_s: u32) -> String {
+    if time_limit_s == 0 {
+        "no time limit".to_string()
+    } else {
+        format!("{} s time limit", time_limit_s)
+    }
+}
+
+///short text of the match rule
+fn match_rule_text(match_rule: MatchRule) -> &'static str {
+    match match_rule {
+        MatchRule::KeepTurn => "a match keeps the turn",
+        MatchRule::PassTurn => "a match passes the turn",
+    }
+}
+
+///all the settings in one line
+pub fn settings_text(settings: &GameSettings) -> String {
+    format!(
+        "{}x{}, {}, {}, {}, auto-flip {}",
+        settings.grid_columns,
+        settings.grid_rows,
+        settings.deck,
+        match_rule_text(settings.match_rule),
+        time_limit_text(settings.time_limit_s),
+        if settings.auto_flip { "on" } else { "off" }
+    )
+}
+
+///one setting that changes on click
+fn div_setting<'a, F>(
+    game_data: &GameData,
+    bump: &'a Bump,
+    label: &str,
+    value: &str,
+    change: F,
+) -> Node<'a>
+where
+    F: 'static + Fn(&mut GameSettings),
+{
+    div(bump)
+        .attr("class", "grid_container_players")
+        .attr("style", "grid-template-columns: auto auto;")
+        .children([
+            div(bump)
+                .attr("class", "grid_item")
+                .children([text(bumpalo::format!(in bump, "{}", label).into_bump_str())])
+                .finish(),
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, Status::Action, bump))
+                .children([text(bumpalo::format!(in bump, "{}", value).into_bump_str())])
+                .finish(),
+        ])
+        .on("click", move |root, vdom, _event| {
+            let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+            let mut game_data = root_rendering_component.rc.borrow_mut();
+            change(&mut game_data.my_settings);
+            vdom.schedule_render();
+        })
+        .finish()
+}
+
+///my settings for the next invitation or hot seat game. Click to change.
+pub fn div_my_settings<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    let settings = &game_data.my_settings;
+    div(bump)
+        .children([
+            h4(bump)
+                .children([text("My settings (click to change):")])
+                .finish(),
+            div_setting(
+                game_data,
+                bump,
+                "grid",
+                &format!("{}x{}", settings.grid_columns, settings.grid_rows),
+                |settings| {
+                    let (grid_columns, grid_rows) =
+                        next_option(GRID_SIZES, &(settings.grid_columns, settings.grid_rows));
+                    settings.grid_columns = grid_columns;
+                    settings.grid_rows = grid_rows;
+                },
+            ),
+            div_setting(game_data, bump, "deck", &settings.deck, |settings| {
+                settings.deck = next_option(DECKS, &settings.deck.as_str()).to_string();
+            }),
+            div_setting(
+                game_data,
+                bump,
+                "match",
+                match_rule_text(settings.match_rule),
+                |settings| {
+                    settings.match_rule = next_option(
+                        &[MatchRule::KeepTurn, MatchRule::PassTurn],
+                        &settings.match_rule,
+                    );
+                },
+            ),
+            div_setting(
+                game_data,
+                bump,
+                "timer",
+                &time_limit_text(settings.time_limit_s),
+                |settings| {
+                    settings.time_limit_s = next_option(TIME_LIMITS_S, &settings.time_limit_s);
+                },
+            ),
+            div_setting(
+                game_data,
+                bump,
+                "auto-flip",
+                if settings.auto_flip { "on" } else { "off" },
+                |settings| settings.auto_flip = !settings.auto_flip,
+            ),
+        ])
+        .finish()
+}
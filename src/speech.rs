@@ -0,0 +1,309 @@
+//! speech.rs - the browser speaks the aviation spelling word of the flipped card.
+//! Kids who cannot read yet hear the word that the header shows. Optionally the letter is spoken before the word.
+//! The language is the active locale of the browser. The voice is chosen among the voices of that language.
+//! The word is spoken after the Morse code, so the two do not sound together.
+//! Speech mode, rate and voice are saved in localStorage.
+
+use crate::indicator::{self, Status};
+use crate::{local_storage, GameData, RootRenderingComponent};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{console, SpeechSynthesis, SpeechSynthesisUtterance, SpeechSynthesisVoice};
+
+///the name of the speech mode item in localStorage
+const SPEECH_MODE_STORAGE_KEY: &str = "mem2_speech_mode";
+///the name of the speech rate item in localStorage
+const SPEECH_RATE_STORAGE_KEY: &str = "mem2_speech_rate";
+///the name of the voice item in localStorage
+const SPEECH_VOICE_STORAGE_KEY: &str = "mem2_speech_voice";
+///every click on the rate goes to the next step. Slow is easier for kids.
+const RATE_STEPS: &[f32] = &[0.6, 0.8, 1.0, 1.2];
+///the browser without a language
+const DEFAULT_LOCALE: &str = "en-US";
+
+///what is spoken on flip
+#[derive(Clone, Copy, PartialEq)]
+pub enum SpeechMode {
+    ///nothing
+    Off,
+    ///the spelling word: "alpha"
+    Word,
+    ///the letter and the word: "A, alpha"
+    LetterAndWord,
+}
+
+impl SpeechMode {
+    ///the value in localStorage
+    fn as_str(self) -> &'static str {
+        match self {
+            SpeechMode::Off => "off",
+            SpeechMode::Word => "word",
+            SpeechMode::LetterAndWord => "letter_and_word",
+        }
+    }
+    ///the text on the screen
+    fn label(self) -> &'static str {
+        match self {
+            SpeechMode::Off => "speak: off",
+            SpeechMode::Word => "speak: word",
+            SpeechMode::LetterAndWord => "speak: letter + word",
+        }
+    }
+}
+
+///the speech settings of the player
+pub struct Speech {
+    ///what is spoken on flip
+    pub mode: SpeechMode,
+    ///1.0 is the normal rate
+    pub rate: f32,
+    ///the language of the browser like "en-US"
+    pub locale: String,
+    ///the chosen voice. None is the default voice of the locale.
+    pub voice_name: Option<String>,
+}
+
+impl Speech {
+    ///the speech settings from localStorage and the locale from the browser
+    pub fn load() -> Self {
+        let storage = local_storage();
+        let get_item = |key: &str| {
+            storage
+                .as_ref()
+                .and_then(|storage| storage.get_item(key).ok())
+                .and_then(|opt| opt)
+        };
+        let mode = match get_item(SPEECH_MODE_STORAGE_KEY)
+            .as_ref()
+            .map(String::as_str)
+        {
+            Some("word") => SpeechMode::Word,
+            Some("letter_and_word") => SpeechMode::LetterAndWord,
+            _ => SpeechMode::Off,
+        };
+        let rate = get_item(SPEECH_RATE_STORAGE_KEY)
+            .and_then(|rate| rate.parse::<f32>().ok())
+            .filter(|rate| RATE_STEPS.contains(rate))
+            .unwrap_or(1.0);
+        let locale = web_sys::window()
+            .and_then(|window| window.navigator().language())
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+        Speech {
+            mode,
+            rate,
+            locale,
+            voice_name: get_item(SPEECH_VOICE_STORAGE_KEY),
+        }
+    }
+
+    ///the speech synthesis of the browser. Some browsers do not have it.
+    fn synthesis() -> Option<SpeechSynthesis> {
+        web_sys::window().and_then(|window| window.speech_synthesis().ok())
+    }
+
+    ///the browser can speak
+    pub fn is_supported() -> bool {
+        Self::synthesis().is_some()
+    }
+
+    ///the voices of the locale: first the exact locale, then the same language in other countries.
+    ///The browser loads the voices lazily. The list can be empty on the first call.
+    fn locale_voices(&self) -> Vec<SpeechSynthesisVoice> {
+        let language = self.locale.split('-').next().unwrap_or("");
+        let mut vec_voices: Vec<SpeechSynthesisVoice> = Self::synthesis()
+            .map(|synthesis| synthesis.get_voices().iter().collect::<Vec<JsValue>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|voice| voice.dyn_into::<SpeechSynthesisVoice>().ok())
+            .filter(|voice| voice.lang().split('-').next().unwrap_or("") == language)
+            .collect();
+        vec_voices.sort_by_key(|voice| voice.lang() != self.locale);
+        vec_voices
+    }
+
+    ///the saved voice if the locale has it, else the first voice of the locale
+    fn voice(&self) -> Option<SpeechSynthesisVoice> {
+        let vec_voices = self.locale_voices();
+        let saved = self.voice_name.as_ref().and_then(|voice_name| {
+            vec_voices
+                .iter()
+                .find(|voice| voice.name() == *voice_name)
+                .cloned()
+        });
+        saved.or_else(|| vec_voices.into_iter().next())
+    }
+
+    ///what to say for the card. None when the speech is off.
+    pub fn utterance_text(&self, letter: char, word: &str) -> Option<String> {
+        match self.mode {
+            SpeechMode::Off => None,
+            SpeechMode::Word => Some(word.to_string()),
+            SpeechMode::LetterAndWord => Some(format!("{}, {}", letter, word)),
+        }
+    }
+
+    ///speak the text after the delay. The text that is still spoken then is interrupted.
+    pub fn speak_after(&self, text: String, delay_ms: f64, volume: f64) {
+        let synthesis = match Self::synthesis() {
+            Some(synthesis) => synthesis,
+            None => return,
+        };
+        let utterance = match SpeechSynthesisUtterance::new_with_text(&text) {
+            Ok(utterance) => utterance,
+            Err(_err) => {
+                console::log_1(&"error: cannot create the speech utterance".into());
+                return;
+            }
+        };
+        utterance.set_lang(&self.locale);
+        utterance.set_rate(self.rate);
+        utterance.set_volume(volume as f32);
+        if let Some(voice) = self.voice() {
+            utterance.set_voice(Some(&voice));
+        }
+        let speak = Closure::once_into_js(move || {
+            synthesis.cancel();
+            synthesis.speak(&utterance);
+        });
+        if let Some(window) = web_sys::window() {
+            if window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                    speak.unchecked_ref(),
+                    delay_ms as i32,
+                )
+                .is_err()
+            {
+                console::log_1(&"error: cannot schedule the speech".into());
+            }
+        }
+    }
+
+    ///off, word, letter and word and again off. Save it.
+    pub fn next_mode(&mut self) {
+        self.mode = match self.mode {
+            SpeechMode::Off => SpeechMode::Word,
+            SpeechMode::Word => SpeechMode::LetterAndWord,
+            SpeechMode::LetterAndWord => SpeechMode::Off,
+        };
+        save_item(SPEECH_MODE_STORAGE_KEY, self.mode.as_str());
+    }
+
+    ///the next rate step and save it. After the fastest comes the slowest.
+    pub fn next_rate(&mut self) {
+        let next = RATE_STEPS
+            .iter()
+            .position(|step| (*step - self.rate).abs() < f32::EPSILON)
+            .map_or(0, |position| (position + 1) % RATE_STEPS.len());
+        self.rate = RATE_STEPS.get(next).copied().unwrap_or(1.0);
+        save_item(SPEECH_RATE_STORAGE_KEY, &self.rate.to_string());
+    }
+
+    ///the next voice of the locale and save it
+    pub fn next_voice(&mut self) {
+        let vec_names: Vec<String> = self
+            .locale_voices()
+            .iter()
+            .map(SpeechSynthesisVoice::name)
+            .collect();
+        let current = self.voice().map(|voice| voice.name());
+        let next = current
+            .and_then(|current| vec_names.iter().position(|name| *name == current))
+            .map_or(0, |position| (position + 1) % vec_names.len().max(1));
+        if let Some(name) = vec_names.get(next) {
+            save_item(SPEECH_VOICE_STORAGE_KEY, name);
+            self.voice_name = Some(name.clone());
+        }
+    }
+
+    ///the short name of the voice on the screen
+    fn voice_label(&self) -> String {
+        self.voice()
+            .map_or_else(|| self.locale.clone(), |voice| voice.name())
+    }
+}
+
+///write one speech setting to localStorage
+fn save_item(key: &str, value: &str) {
+    if let Some(storage) = local_storage() {
+        if storage.set_item(key, value).is_err() {
+            console::log_1(&"error: cannot save the speech settings to localStorage".into());
+        }
+    }
+}
+
+///speech mode, rate and voice. Click to change. Nothing if the browser cannot speak.
+pub fn div_speech_settings<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    let speech = &game_data.speech;
+    if !Speech::is_supported() {
+        return div(bump).finish();
+    }
+    let mode_status = if speech.mode == SpeechMode::Off {
+        Status::Negative
+    } else {
+        Status::Positive
+    };
+    let mut vec_settings = vec![div(bump)
+        .attr("class", "grid_item")
+        .attr("style", indicator::style(game_data, mode_status, bump))
+        .children([text(indicator::label(
+            game_data,
+            mode_status,
+            speech.mode.label(),
+            bump,
+        ))])
+        .on("click", move |root, vdom, _event| {
+            let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+            root_rendering_component.rc.borrow_mut().speech.next_mode();
+            vdom.schedule_render();
+        })
+        .finish()];
+    if speech.mode != SpeechMode::Off {
+        vec_settings.push(
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, Status::Action, bump))
+                .children([text(
+                    bumpalo::format!(in bump, "rate: {:.1}x", speech.rate).into_bump_str(),
+                )])
+                .on("click", move |root, vdom, _event| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    game_data.speech.next_rate();
+                    //the player hears the new rate
+                    game_data.speak_sample();
+                    vdom.schedule_render();
+                })
+                .finish(),
+        );
+        vec_settings.push(
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, Status::Action, bump))
+                .children([text(
+                    bumpalo::format!(in bump, "voice: {}", speech.voice_label()).into_bump_str(),
+                )])
+                .on("click", move |root, vdom, _event| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    game_data.speech.next_voice();
+                    //the player hears the new voice
+                    game_data.speak_sample();
+                    vdom.schedule_render();
+                })
+                .finish(),
+        );
+    }
+    let grid_template_columns = if speech.mode == SpeechMode::Off {
+        "grid-template-columns: auto;"
+    } else {
+        "grid-template-columns: auto auto auto;"
+    };
+    div(bump)
+        .attr("class", "grid_container_players")
+        .attr("style", grid_template_columns)
+        .children(vec_settings)
+        .finish()
+}
@@ -0,0 +1,222 @@
+//! theme.rs - the look of the game: card back, colours, fonts, light or dark.
+//! A theme is only a definition in `THEMES`. The render functions read the colours of the status
+//! through the indicator module and the card back from the theme.
+//! The background, the text colour and the fonts are CSS variables on the html element,
+//! so mem2.css uses them everywhere without inline styles.
+//! The theme is chosen in the settings and saved in localStorage.
+
+use crate::indicator::{self, ColorScheme, Status};
+use crate::{local_storage, GameData, RootRenderingComponent};
+use dodrio::builder::*;
+use dodrio::bumpalo::{self, Bump};
+use dodrio::Node;
+use wasm_bindgen::JsCast;
+use web_sys::console;
+
+///the name of the item in localStorage
+const THEME_STORAGE_KEY: &str = "mem2_theme";
+
+///the colours of the theme. The standard colour scheme uses them for the status.
+pub struct Palette {
+    ///the page
+    pub background: &'static str,
+    ///the normal text
+    pub text: &'static str,
+    ///a match, a right answer, online, whose turn it is
+    pub positive: &'static str,
+    ///one card waits for its pair, lagging
+    pub caution: &'static str,
+    ///a mismatch, a wrong answer, gone, who waits for the turn
+    pub negative: &'static str,
+    ///a letter never found
+    pub inactive: &'static str,
+    ///a text to click
+    pub action: &'static str,
+    ///a text to read
+    pub notice: &'static str,
+}
+
+///the image of the face down card
+pub enum CardBack {
+    ///the card face down image of the deck
+    Deck,
+    ///an image of the theme, the path from the root of the site
+    Image(&'static str),
+}
+
+///everything that makes the look of the game
+pub struct Theme {
+    ///the value in localStorage and the text on the screen
+    pub name: &'static str,
+    ///the face down card
+    pub card_back: CardBack,
+    ///the face down cards are dimmed so much
+    pub card_back_opacity: f64,
+    ///the colours
+    pub palette: Palette,
+    ///the css font-family of the text
+    pub font_family: &'static str,
+    ///the css font-family of the headings
+    pub heading_font_family: &'static str,
+    ///dark background with light text
+    pub is_dark: bool,
+}
+
+///all the themes. The first one is the default. A new theme needs only a definition here.
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "night",
+        card_back: CardBack::Deck,
+        card_back_opacity: 0.2,
+        palette: Palette {
+            background: "#000000",
+            text: "#FBF462",
+            positive: "green",
+            caution: "orange",
+            negative: "red",
+            inactive: "grey",
+            action: "green",
+            notice: "red",
+        },
+        font_family: "Verdana, sans-serif",
+        heading_font_family: "\"Segoe UI\", Arial, sans-serif",
+        is_dark: true,
+    },
+    Theme {
+        name: "day",
+        card_back: CardBack::Image("themes/card_back_day.svg"),
+        card_back_opacity: 1.0,
+        palette: Palette {
+            background: "#FAF7EE",
+            text: "#2B2B2B",
+            positive: "#1B7A32",
+            caution: "#A65E00",
+            negative: "#B3261E",
+            inactive: "#7A7A7A",
+            action: "#1F5FA8",
+            notice: "#B3261E",
+        },
+        font_family: "Verdana, sans-serif",
+        heading_font_family: "Georgia, serif",
+        is_dark: false,
+    },
+    Theme {
+        name: "ocean",
+        card_back: CardBack::Image("themes/card_back_ocean.svg"),
+        card_back_opacity: 0.8,
+        palette: Palette {
+            background: "#0B2545",
+            text: "#EEF4ED",
+            positive: "#7BE0A0",
+            caution: "#F4D35E",
+            negative: "#FF7B6B",
+            inactive: "#8DA9C4",
+            action: "#7FD4F5",
+            notice: "#FF7B6B",
+        },
+        font_family: "\"Trebuchet MS\", Helvetica, sans-serif",
+        heading_font_family: "\"Trebuchet MS\", Helvetica, sans-serif",
+        is_dark: true,
+    },
+];
+
+///the default theme
+fn default_theme() -> &'static Theme {
+    &THEMES[0]
+}
+
+///the theme from localStorage. An unknown name is the default theme.
+pub fn load() -> &'static Theme {
+    local_storage()
+        .and_then(|storage| storage.get_item(THEME_STORAGE_KEY).ok())
+        .and_then(|opt| opt)
+        .and_then(|name| THEMES.iter().find(|theme| theme.name == name))
+        .unwrap_or_else(default_theme)
+}
+
+///the next theme and save it. After the last comes the first.
+pub fn next(current: &Theme) -> &'static Theme {
+    let position = THEMES.iter().position(|theme| theme.name == current.name);
+    let next = position.map_or(0, |position| (position + 1) % THEMES.len());
+    let theme = THEMES.get(next).unwrap_or_else(default_theme);
+    if let Some(storage) = local_storage() {
+        if storage.set_item(THEME_STORAGE_KEY, theme.name).is_err() {
+            console::log_1(&"error: cannot save the theme to localStorage".into());
+        }
+    }
+    theme
+}
+
+///the src of the face down card of the deck
+pub fn card_back_src(theme: &Theme, deck: &str, src_for_card_face_down: &str) -> String {
+    match theme.card_back {
+        CardBack::Deck => format!("{}/{}", deck, src_for_card_face_down),
+        CardBack::Image(src) => src.to_string(),
+    }
+}
+
+///write the page colours and the fonts as CSS variables on the html element.
+///The high contrast scheme makes the background black or white.
+pub fn apply(theme: &Theme, color_scheme: ColorScheme) {
+    let (background, text) = match (color_scheme, theme.is_dark) {
+        (ColorScheme::HighContrast, true) => ("#000000", "#FFFFFF"),
+        (ColorScheme::HighContrast, false) => ("#FFFFFF", "#000000"),
+        _ => (theme.palette.background, theme.palette.text),
+    };
+    let style = match web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.document_element())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok())
+    {
+        Some(element) => element.style(),
+        None => return,
+    };
+    for (property, value) in &[
+        ("--mem2-background", background),
+        ("--mem2-text", text),
+        ("--mem2-font", theme.font_family),
+        ("--mem2-heading-font", theme.heading_font_family),
+        //the scrollbars and the form controls of the browser follow the theme
+        ("color-scheme", if theme.is_dark { "dark" } else { "light" }),
+    ] {
+        if style.set_property(property, value).is_err() {
+            console::log_1(&format!("error: cannot set the css property {}", property).into());
+        }
+    }
+}
+
+///the theme and the colour scheme. Click to change.
+pub fn div_theme_settings<'a>(game_data: &GameData, bump: &'a Bump) -> Node<'a> {
+    div(bump)
+        .attr("class", "grid_container_players")
+        .attr("style", "grid-template-columns: auto auto;")
+        .children([
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, Status::Action, bump))
+                .children([text(
+                    bumpalo::format!(in bump, "theme: {}", game_data.theme.name).into_bump_str(),
+                )])
+                .on("click", move |root, vdom, _event| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    game_data.theme = next(game_data.theme);
+                    apply(game_data.theme, game_data.color_scheme);
+                    vdom.schedule_render();
+                })
+                .finish(),
+            div(bump)
+                .attr("class", "grid_item")
+                .attr("style", indicator::style(game_data, Status::Action, bump))
+                .children([text(game_data.color_scheme.label())])
+                .on("click", move |root, vdom, _event| {
+                    let root_rendering_component = root.unwrap_mut::<RootRenderingComponent>();
+                    let mut game_data = root_rendering_component.rc.borrow_mut();
+                    game_data.color_scheme.next();
+                    apply(game_data.theme, game_data.color_scheme);
+                    vdom.schedule_render();
+                })
+                .finish(),
+        ])
+        .finish()
+}
@@ -0,0 +1,123 @@
+//! tone.rs - the Morse tone generator with Web Audio.
+//! One sine oscillator sounds the whole text. Its gain is switched on and off for every dot and dash
+//! on the audio clock, so the timing does not depend on the busy main thread.
+//! The short ramps at the edges of every tone avoid the clicks in the speaker.
+//! The `AudioContext` is created on the first play. It is a click of the player, so the autoplay policy allows it.
+
+use crate::error::{Mem2Error, Mem2Result};
+use futures::Future;
+use mem2_engine::morse;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{console, AudioContext, AudioContextState, OscillatorNode, OscillatorType};
+
+///the first tone starts a little later, so the gain schedule is ready
+pub const LEAD_IN_S: f64 = 0.05;
+///the edges of every tone
+const RAMP_S: f64 = 0.005;
+
+///sounds the Morse code of any text
+pub struct ToneGenerator {
+    ///created on the first play
+    context: Option<AudioContext>,
+    ///the text that is still sounding is stopped by the next one
+    oscillator: Option<OscillatorNode>,
+}
+
+impl ToneGenerator {
+    ///nothing is created before the first play
+    pub fn new() -> Self {
+        ToneGenerator {
+            context: None,
+            oscillator: None,
+        }
+    }
+
+    ///old browsers do not have Web Audio. They play the mp3 files.
+    pub fn is_supported() -> bool {
+        web_sys::window()
+            .and_then(|window| js_sys::Reflect::get(&window, &"AudioContext".into()).ok())
+            .map_or(false, |constructor| constructor.is_function())
+    }
+
+    ///the audio context is created once
+    fn context(&mut self) -> Mem2Result<AudioContext> {
+        if let Some(context) = &self.context {
+            return Ok(context.clone());
+        }
+        let context =
+            AudioContext::new().map_err(|err| Mem2Error::Audio(Mem2Error::js_message(&err)))?;
+        self.context = Some(context.clone());
+        Ok(context)
+    }
+
+    ///play the Morse code of the text from the start. A text that is still sounding stops.
+    ///The pitch is in Hz and the volume from 0.0 to 1.0.
+    pub fn play_text(
+        &mut self,
+        text: &str,
+        wpm: u32,
+        pitch_hz: u32,
+        volume: f64,
+    ) -> Mem2Result<()> {
+        let vec_tones = morse::tones(text, wpm);
+        if vec_tones.is_empty() {
+            return Ok(());
+        }
+        let context = self.context()?;
+        //a context created without a user click waits in the suspended state
+        if context.state() == AudioContextState::Suspended {
+            let promise = context
+                .resume()
+                .map_err(|err| Mem2Error::Audio(Mem2Error::js_message(&err)))?;
+            wasm_bindgen_futures::spawn_local(JsFuture::from(promise).map(|_| ()).map_err(|err| {
+                console::log_1(
+                    &format!("audio not resumed: {}", Mem2Error::js_message(&err)).into(),
+                );
+            }));
+        }
+        self.stop();
+
+        let js_err = |err| Mem2Error::Audio(Mem2Error::js_message(&err));
+        let oscillator = context.create_oscillator().map_err(js_err)?;
+        oscillator.set_type(OscillatorType::Sine);
+        oscillator.frequency().set_value(pitch_hz as f32);
+        let gain_node = context.create_gain().map_err(js_err)?;
+        let gain = gain_node.gain();
+        let volume = volume as f32;
+
+        let start_s = context.current_time() + LEAD_IN_S;
+        gain.set_value_at_time(0.0, start_s).map_err(js_err)?;
+        for tone in &vec_tones {
+            let on_s = start_s + tone.start_ms / 1000.0;
+            let off_s = on_s + tone.duration_ms / 1000.0;
+            gain.set_value_at_time(0.0, on_s).map_err(js_err)?;
+            gain.linear_ramp_to_value_at_time(volume, on_s + RAMP_S)
+                .map_err(js_err)?;
+            gain.set_value_at_time(volume, off_s - RAMP_S)
+                .map_err(js_err)?;
+            gain.linear_ramp_to_value_at_time(0.0, off_s)
+                .map_err(js_err)?;
+        }
+
+        oscillator
+            .connect_with_audio_node(&gain_node)
+            .map_err(js_err)?;
+        gain_node
+            .connect_with_audio_node(&context.destination())
+            .map_err(js_err)?;
+        oscillator.start_with_when(start_s).map_err(js_err)?;
+        oscillator
+            .stop_with_when(start_s + morse::duration_ms(&vec_tones) / 1000.0 + RAMP_S)
+            .map_err(js_err)?;
+        self.oscillator = Some(oscillator);
+        Ok(())
+    }
+
+    ///silence the text that is still sounding
+    pub fn stop(&mut self) {
+        if let Some(oscillator) = self.oscillator.take() {
+            //it can be already stopped. That is fine.
+            let _ = oscillator.stop();
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! transport.rs - the game messages can travel over different transports.
+//! `WebSocket` is the normal one and needs the mem2_server.
+//! `BroadcastChannel` connects two tabs of the same browser on one machine without any server.
+//! The game code knows only the `Transport` trait. The choice is made once at startup.
+//! Every transport carries text frames with json and binary frames with the compact encoding.
+//! A closed WebSocket can be opened again with `reconnect`. The game then sends again what was not acknowledged.
+
+use crate::error::{Mem2Error, Mem2Result};
+use js_sys::Uint8Array;
+use mem2_engine::{query_value, WireFormat};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{console, BinaryType, BroadcastChannel, MessageEvent, WebSocket};
+
+///the name of the BroadcastChannel. Only tabs with the same name can talk.
+const BROADCAST_CHANNEL_NAME: &str = "mem2";
+
+///one received message: json text or compact binary
+pub enum Frame {
+    ///json
+    Text(String),
+    ///bincode
+    Binary(Vec<u8>),
+}
+
+///callback for a received message
+pub type MsgHandler = Box<dyn Fn(Frame)>;
+///callback for the connection events open and close
+pub type EventHandler = Box<dyn Fn()>;
+
+///everything the game needs to send and receive messages
+pub trait Transport {
+    ///send a text message to the other players
+    fn send(&self, msg: &str) -> Mem2Result<()>;
+    ///send a binary message to the other players
+    fn send_binary(&self, msg: &[u8]) -> Mem2Result<()>;
+    ///the handler is called for every received text or binary message
+    fn set_on_message(&self, handler: MsgHandler);
+    ///the handler is called when the connection is ready to send
+    fn set_on_open(&self, handler: EventHandler);
+    ///the handler is called when the connection is closed
+    fn set_on_close(&self, handler: EventHandler);
+    ///true if the messages can be sent now
+    fn is_open(&self) -> bool;
+    ///open a new connection if the old one is closed. The handlers stay the same.
+    fn reconnect(&self) -> Mem2Result<()>;
+    ///true if the other end is the server. Only the server sends the server-authoritative game.
+    fn is_server_connection(&self) -> bool {
+        false
+    }
+}
+
+///the transports that can be chosen at startup
+pub enum TransportKind {
+    ///WebSocket to the mem2_server
+    WebSocket,
+    ///BroadcastChannel between tabs of the same browser
+    BroadcastChannel,
+}
+
+impl TransportKind {
+    ///the transport is chosen with the url query `?transport=broadcast`.
+    ///Everything else is WebSocket.
+    pub fn from_location_search(search: &str) -> Self {
+        match query_value(search, "transport") {
+            Some("broadcast") => TransportKind::BroadcastChannel,
+            _ => TransportKind::WebSocket,
+        }
+    }
+}
+
+///the wire format this client proposes in the handshake. `?encoding=json` is easier to debug.
+///It can stand together with other queries like `?transport=broadcast&encoding=json`.
+pub fn wire_format_from_location_search(search: &str) -> WireFormat {
+    if query_value(search, "encoding") == Some("json") {
+        WireFormat::Json
+    } else {
+        WireFormat::Binary
+    }
+}
+
+///the address of the WebSocket on the same server and port as the page.
+///Only the protocol and the host are used. The path, the query and the fragment of the page stay in the browser.
+pub fn web_socket_url(location_protocol: &str, location_host: &str) -> String {
+    let ws_protocol = if location_protocol == "https:" {
+        "wss:"
+    } else {
+        "ws:"
+    };
+    format!("{}//{}/mem2ws/", ws_protocol, location_host)
+}
+
+///construct the transport chosen at startup
+pub fn new_transport(kind: &TransportKind, ws_url: &str) -> Mem2Result<Rc<dyn Transport>> {
+    Ok(match kind {
+        TransportKind::WebSocket => Rc::new(WebSocketTransport::new(ws_url)?),
+        TransportKind::BroadcastChannel => Rc::new(BroadcastChannelTransport::new()?),
+    })
+}
+
+///the received data is a string or binary. WebSocket gives an ArrayBuffer, BroadcastChannel a Uint8Array.
+fn frame_from_js(data: &JsValue) -> Option<Frame> {
+    if let Some(text) = data.as_string() {
+        Some(Frame::Text(text))
+    } else if data.is_instance_of::<js_sys::ArrayBuffer>() || data.is_instance_of::<Uint8Array>() {
+        let array = Uint8Array::new(data);
+        let mut bytes = vec![0; array.length() as usize];
+        array.copy_to(&mut bytes);
+        Some(Frame::Binary(bytes))
+    } else {
+        None
+    }
+}
+
+///the web-sys errors are JsValue
+fn transport_error(js_value: &JsValue) -> Mem2Error {
+    Mem2Error::Transport(Mem2Error::js_message(js_value))
+}
+
+//region: WebSocket
+///WebSocket to the mem2_server
+pub struct WebSocketTransport {
+    ///the address of the WebSocket, for the reconnect. It has no query of the page,
+    ///so a page opened with `?disconnect_timeout=` reconnects to the same server path.
+    url: String,
+    ///web-sys has websocket for Rust exactly like javascript has. The reconnect replaces it.
+    ws: RefCell<WebSocket>,
+    ///the handlers are kept to attach them to the new WebSocket after a reconnect
+    cb_message: RefCell<Option<Closure<dyn Fn(JsValue)>>>,
+    ///open handler
+    cb_open: RefCell<Option<Closure<dyn Fn()>>>,
+    ///close handler
+    cb_close: RefCell<Option<Closure<dyn Fn()>>>,
+}
+
+///open a WebSocket that receives the binary frames as ArrayBuffer, not as Blob
+fn open_web_socket(url: &str) -> Mem2Result<WebSocket> {
+    let ws = WebSocket::new(url).map_err(|err| transport_error(&err))?;
+    ws.set_binary_type(BinaryType::Arraybuffer);
+    Ok(ws)
+}
+
+impl WebSocketTransport {
+    ///setup websocket connection
+    ///ws_url comes in this format  ws://localhost:4000/mem2ws/
+    pub fn new(ws_url: &str) -> Mem2Result<Self> {
+        //Only for debugging in the development environment
+        //let ws_url = "ws://192.168.1.57:80/mem2ws/";
+        console::log_1(&wasm_bindgen::JsValue::from_str(ws_url));
+        //same server address and port as http server
+        let ws = open_web_socket(ws_url)?;
+        Ok(WebSocketTransport {
+            url: ws_url.to_string(),
+            ws: RefCell::new(ws),
+            cb_message: RefCell::new(None),
+            cb_open: RefCell::new(None),
+            cb_close: RefCell::new(None),
+        })
+    }
+    ///the kept handlers are attached to this WebSocket
+    fn attach_handlers(&self, ws: &WebSocket) {
+        if let Some(cb_mrh) = self.cb_message.borrow().as_ref() {
+            ws.set_onmessage(Some(cb_mrh.as_ref().unchecked_ref()));
+        }
+        if let Some(cb_oh) = self.cb_open.borrow().as_ref() {
+            ws.set_onopen(Some(cb_oh.as_ref().unchecked_ref()));
+        }
+        if let Some(cb_ch) = self.cb_close.borrow().as_ref() {
+            ws.set_onclose(Some(cb_ch.as_ref().unchecked_ref()));
+        }
+    }
+}
+
+impl Transport for WebSocketTransport {
+    ///sending on a closed WebSocket returns an error
+    fn send(&self, msg: &str) -> Mem2Result<()> {
+        self.ws
+            .borrow()
+            .send_with_str(msg)
+            .map_err(|err| transport_error(&err))
+    }
+    fn send_binary(&self, msg: &[u8]) -> Mem2Result<()> {
+        //web-sys wants a mutable slice
+        let mut msg = msg.to_vec();
+        self.ws
+            .borrow()
+            .send_with_u8_array(&mut msg)
+            .map_err(|err| transport_error(&err))
+    }
+    fn set_on_message(&self, handler: MsgHandler) {
+        let msg_recv_handler = Box::new(move |msg: JsValue| {
+            match js_sys::Reflect::get(&msg, &"data".into())
+                .ok()
+                .and_then(|data| frame_from_js(&data))
+            {
+                Some(frame) => handler(frame),
+                None => console::log_1(&"websocket message without text or binary data".into()),
+            }
+        });
+        let cb_mrh: Closure<dyn Fn(JsValue)> = Closure::wrap(msg_recv_handler);
+        self.ws
+            .borrow()
+            .set_onmessage(Some(cb_mrh.as_ref().unchecked_ref()));
+        //don't drop the eventlistener from memory while the transport lives
+        *self.cb_message.borrow_mut() = Some(cb_mrh);
+    }
+    fn set_on_open(&self, handler: EventHandler) {
+        let cb_oh: Closure<dyn Fn()> = Closure::wrap(handler);
+        self.ws
+            .borrow()
+            .set_onopen(Some(cb_oh.as_ref().unchecked_ref()));
+        *self.cb_open.borrow_mut() = Some(cb_oh);
+    }
+    fn set_on_close(&self, handler: EventHandler) {
+        let cb_ch: Closure<dyn Fn()> = Closure::wrap(handler);
+        self.ws
+            .borrow()
+            .set_onclose(Some(cb_ch.as_ref().unchecked_ref()));
+        *self.cb_close.borrow_mut() = Some(cb_ch);
+    }
+    fn is_open(&self) -> bool {
+        self.ws.borrow().ready_state() == WebSocket::OPEN
+    }
+    ///the relay drops the server messages sent by clients
+    fn is_server_connection(&self) -> bool {
+        true
+    }
+    ///a WebSocket that is still connecting or closing is left alone
+    fn reconnect(&self) -> Mem2Result<()> {
+        if self.ws.borrow().ready_state() != WebSocket::CLOSED {
+            return Ok(());
+        }
+        console::log_1(&"reconnect WebSocket".into());
+        let ws = open_web_socket(&self.url)?;
+        self.attach_handlers(&ws);
+        *self.ws.borrow_mut() = ws;
+        Ok(())
+    }
+}
+//endregion
+
+//region: BroadcastChannel
+///BroadcastChannel between tabs of the same browser. The sender does not receive its own messages.
+pub struct BroadcastChannelTransport {
+    ///the channel has a fixed name
+    channel: BroadcastChannel,
+    ///the handler is kept while the transport lives. A new handler drops the old one.
+    cb_message: RefCell<Option<Closure<dyn Fn(MessageEvent)>>>,
+}
+
+impl BroadcastChannelTransport {
+    ///open the channel
+    pub fn new() -> Mem2Result<Self> {
+        let channel =
+            BroadcastChannel::new(BROADCAST_CHANNEL_NAME).map_err(|err| transport_error(&err))?;
+        Ok(BroadcastChannelTransport {
+            channel,
+            cb_message: RefCell::new(None),
+        })
+    }
+}
+
+impl Transport for BroadcastChannelTransport {
+    fn send(&self, msg: &str) -> Mem2Result<()> {
+        self.channel
+            .post_message(&JsValue::from_str(msg))
+            .map_err(|err| transport_error(&err))
+    }
+    ///the bytes are copied into a Uint8Array
+    fn send_binary(&self, msg: &[u8]) -> Mem2Result<()> {
+        let array = Uint8Array::from(msg);
+        self.channel
+            .post_message(&array)
+            .map_err(|err| transport_error(&err))
+    }
+    fn set_on_message(&self, handler: MsgHandler) {
+        let msg_recv_handler = Box::new(move |event: MessageEvent| {
+            if let Some(frame) = frame_from_js(&event.data()) {
+                handler(frame);
+            }
+        });
+        let cb_mrh: Closure<dyn Fn(MessageEvent)> = Closure::wrap(msg_recv_handler);
+        self.channel
+            .set_onmessage(Some(cb_mrh.as_ref().unchecked_ref()));
+        //don't drop the eventlistener from memory while the transport lives
+        *self.cb_message.borrow_mut() = Some(cb_mrh);
+    }
+    ///the channel is open immediately
+    fn set_on_open(&self, handler: EventHandler) {
+        handler();
+    }
+    ///the channel is never closed by the other side
+    fn set_on_close(&self, _handler: EventHandler) {}
+    fn is_open(&self) -> bool {
+        true
+    }
+    fn reconnect(&self) -> Mem2Result<()> {
+        Ok(())
+    }
+}
+//endregion